@@ -1,4 +1,9 @@
-use chessie::{eval::Scorer, fen, search::Search, util::print_board};
+use chessie::{
+    eval::Scorer,
+    fen,
+    search::{Search, TranspositionTable},
+    util::print_board,
+};
 use std::{fs::File, io::Write, time::Instant};
 
 fn main() {
@@ -23,20 +28,29 @@ fn main() {
     let depth = 6;
 
     let mut file = File::create(format!("bench_results_depth{}.csv", depth)).unwrap();
-    writeln!(file, "name,duration_ms,nodes,nodes_sec").unwrap();
+    writeln!(file, "name,duration_ms,nodes,nodes_sec,qnodes").unwrap();
     for (scenario_name, fen_str) in positions {
         let board = fen::decode(fen_str).unwrap();
         print_board(&board);
 
         let mut nodes = 0usize;
         let start = Instant::now();
-        let search = Search::new(&board, depth, Scorer::eval);
+        let mut tt = TranspositionTable::default();
+        let search = Search::new(&board, depth, Scorer::eval).with_table(&mut tt);
         let (_, stats) = search.find_with_stats();
         let duration = start.elapsed();
         let secs = duration.as_secs_f64();
-        let nps = (stats.nodes - nodes) as f64 / secs;
+        let nps = (stats.nodes + stats.qnodes - nodes) as f64 / secs;
         nodes = stats.nodes;
-        writeln!(file, "\"{}\",{},{},{}", scenario_name, duration.as_millis(), nodes, nps as u64)
-            .unwrap();
+        writeln!(
+            file,
+            "\"{}\",{},{},{},{}",
+            scenario_name,
+            duration.as_millis(),
+            nodes,
+            nps as u64,
+            stats.qnodes
+        )
+        .unwrap();
     }
 }
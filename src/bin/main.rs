@@ -1,3 +1,19 @@
+use std::{env, process};
+
+/// Engine entry point. GUIs must pass the protocol as the first argument,
+/// e.g. `chessie uci`: without arguments the engine plays itself and never
+/// reads stdin.
 fn main() {
-    chessie::main();
+    match env::args().nth(1).as_deref() {
+        Some("uci") => chessie::uci::run(),
+        Some("xboard") => chessie::cecp::run(),
+        None => chessie::main(),
+        Some(_) => {
+            eprintln!("usage: chessie [uci|xboard]");
+            eprintln!("  uci     speak UCI over stdin and stdout");
+            eprintln!("  xboard  speak XBoard/CECP over stdin and stdout");
+            eprintln!("  without arguments, plays a self-play game and prints its PGN");
+            process::exit(2);
+        }
+    }
 }
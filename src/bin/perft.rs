@@ -0,0 +1,38 @@
+use std::{env, process, time::Instant};
+
+use chessie::{fen, perft};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let divide = args.iter().any(|arg| arg == "--divide");
+    let args: Vec<&String> = args.iter().filter(|arg| *arg != "--divide").collect();
+
+    let (Some(input), Some(depth)) = (args.first(), args.get(1).and_then(|d| d.parse().ok()))
+    else {
+        eprintln!("usage: chessie-perft <fen> <depth> [--divide]");
+        process::exit(2);
+    };
+    let board = fen::decode(input).unwrap_or_else(|err| {
+        eprintln!("invalid fen: {err}");
+        process::exit(2);
+    });
+
+    let start = Instant::now();
+    let nodes = if divide && depth > 0 {
+        let divided = perft::divide(&board, depth);
+        for (movement, nodes) in &divided {
+            println!("{movement}: {nodes}");
+        }
+        println!();
+        divided.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        perft::perft(&board, depth)
+    };
+    let elapsed = start.elapsed();
+
+    println!("nodes {nodes}");
+    println!("time {}ms", elapsed.as_millis());
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let nps = (nodes as f64 / elapsed.as_secs_f64().max(0.001)) as u64;
+    println!("nps {nps}");
+}
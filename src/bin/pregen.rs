@@ -134,6 +134,214 @@ pub fn antidiag_slider() -> [BitBoard; 64] {
     sq_to_moves
 }
 
+/// Directions a rook slides in, as (file, rank) steps.
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+
+/// Directions a bishop slides in, as (file, rank) steps.
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+/// Squares attacked from `sq` along `directions`, walking each ray up to
+/// and including the first square in `occupancy`.
+#[must_use]
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+pub fn slider_attacks(sq: Sq, occupancy: BitBoard, directions: &[(i8, i8)]) -> BitBoard {
+    let mut attacks = 0;
+    for (file_step, rank_step) in directions {
+        let (mut file, mut rank) = (pos::col(sq) as i8, pos::row(sq) as i8);
+        loop {
+            file += file_step;
+            rank += rank_step;
+            if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+                break;
+            }
+            let target = pos::bb((rank * 8 + file) as Sq);
+            attacks |= target;
+            if occupancy & target != 0 {
+                break;
+            }
+        }
+    }
+    attacks
+}
+
+/// Occupancy bits that can change a slider's attacks from `sq`: its rays
+/// without the final square on the board edge, which is attacked whether
+/// or not it is occupied.
+#[must_use]
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+pub fn relevant_mask(sq: Sq, directions: &[(i8, i8)]) -> BitBoard {
+    let mut mask = 0;
+    for (file_step, rank_step) in directions {
+        let (mut file, mut rank) = (pos::col(sq) as i8, pos::row(sq) as i8);
+        loop {
+            let (next_file, next_rank) = (file + file_step, rank + rank_step);
+            if !(0..8).contains(&next_file) || !(0..8).contains(&next_rank) {
+                break;
+            }
+            if file != pos::col(sq) as i8 || rank != pos::row(sq) as i8 {
+                mask |= pos::bb((rank * 8 + file) as Sq);
+            }
+            (file, rank) = (next_file, next_rank);
+        }
+    }
+    mask
+}
+
+/// Magic lookup for one square: `((occupancy & mask) * magic) >> shift`
+/// indexes its attacks, stored from `offset` in the shared table.
+#[derive(Debug, Clone, Copy)]
+pub struct Magic {
+    pub mask: BitBoard,
+    pub magic: u64,
+    pub shift: u8,
+    pub offset: usize,
+}
+
+/// Deterministic xorshift generator, so regenerating the tables gives the
+/// same magics every time.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Candidates with few bits set are far more likely to be magic.
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+/// Finds a magic multiplier for every square and fills the attack table
+/// they index into, returning both.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn magics(directions: &[(i8, i8)], seed: u64) -> (Vec<Magic>, Vec<BitBoard>) {
+    let mut rng = Rng(seed);
+    let mut magics = Vec::with_capacity(64);
+    let mut table = Vec::new();
+
+    for sq in 0..64 {
+        let mask = relevant_mask(sq, directions);
+        let bits = mask.count_ones();
+        let shift = (64 - bits) as u8;
+
+        // Every subset of the mask, enumerated with the carry-rippler trick.
+        let mut occupancies = Vec::with_capacity(1 << bits);
+        let mut occupancy: BitBoard = 0;
+        loop {
+            occupancies.push((occupancy, slider_attacks(sq, occupancy, directions)));
+            occupancy = occupancy.wrapping_sub(mask) & mask;
+            if occupancy == 0 {
+                break;
+            }
+        }
+
+        let mut attacks = vec![0; 1 << bits];
+        let magic = loop {
+            let magic = rng.sparse();
+            if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+                continue;
+            }
+            attacks.fill(0);
+            let collides = occupancies.iter().any(|(occupancy, attack)| {
+                let index = (occupancy.wrapping_mul(magic) >> shift) as usize;
+                let stored = attacks[index];
+                attacks[index] = *attack;
+                stored != 0 && stored != *attack
+            });
+            if !collides {
+                break magic;
+            }
+        };
+
+        magics.push(Magic { mask, magic, shift, offset: table.len() });
+        table.extend(attacks);
+    }
+    (magics, table)
+}
+
+fn emit_slider_attacks() {
+    let (rook, rook_table) = magics(&ROOK_DIRECTIONS, 0x9e37_79b9_7f4a_7c15);
+    let (bishop, bishop_table) = magics(&BISHOP_DIRECTIONS, 0xd1b5_4a32_d192_ed03);
+
+    let content = format!(
+        "/// DO NOT EDIT THIS FILE MANUALLY
+/// This file is auto-generated by `cargo run --bin chessie-pregen` (or `make pregen`)
+///
+/// Magic bitboard tables for sliders. For a slider on a square, the occupancy bits
+/// under its mask are multiplied by its magic and shifted, which indexes its attacks
+/// from its offset in the attack table. The tables are statics as they are too large
+/// to be copied around as constants.
+
+use crate::defs::BitBoard;
+
+{rook_masks}
+{rook_magics}
+{rook_shifts}
+{rook_offsets}
+{rook_attacks}
+{bishop_masks}
+{bishop_magics}
+{bishop_shifts}
+{bishop_offsets}
+{bishop_attacks}
+",
+        rook_masks =
+            emit_static("rook_masks", "BitBoard", &rook.iter().map(|m| m.mask).collect::<Vec<_>>()),
+        rook_magics =
+            emit_static("rook_magics", "u64", &rook.iter().map(|m| m.magic).collect::<Vec<_>>()),
+        rook_shifts = emit_static(
+            "rook_shifts",
+            "u8",
+            &rook.iter().map(|m| u64::from(m.shift)).collect::<Vec<_>>()
+        ),
+        rook_offsets = emit_static(
+            "rook_offsets",
+            "usize",
+            &rook.iter().map(|m| m.offset as u64).collect::<Vec<_>>()
+        ),
+        rook_attacks = emit_static("rook_attacks", "BitBoard", &rook_table),
+        bishop_masks = emit_static(
+            "bishop_masks",
+            "BitBoard",
+            &bishop.iter().map(|m| m.mask).collect::<Vec<_>>()
+        ),
+        bishop_magics = emit_static(
+            "bishop_magics",
+            "u64",
+            &bishop.iter().map(|m| m.magic).collect::<Vec<_>>()
+        ),
+        bishop_shifts = emit_static(
+            "bishop_shifts",
+            "u8",
+            &bishop.iter().map(|m| u64::from(m.shift)).collect::<Vec<_>>()
+        ),
+        bishop_offsets = emit_static(
+            "bishop_offsets",
+            "usize",
+            &bishop.iter().map(|m| m.offset as u64).collect::<Vec<_>>()
+        ),
+        bishop_attacks = emit_static("bishop_attacks", "BitBoard", &bishop_table),
+    );
+
+    fs::write("src/magic/slider_attacks.rs", content).unwrap();
+}
+
+fn emit_static(name: &str, ty: &str, values: &[u64]) -> String {
+    let mut out =
+        format!("pub(crate) static {}: [{ty}; {}] = [\n", name.to_uppercase(), values.len());
+
+    for value in values {
+        writeln!(&mut out, "  0x{value:x},").unwrap();
+    }
+    writeln!(&mut out, "];").unwrap();
+    out
+}
+
 fn emit_file() {
     let content = format!(
         "/// DO NOT EDIT THIS FILE MANUALLY
@@ -165,6 +373,7 @@ impl MagicMovements {{
     );
 
     fs::write("src/magic/magic_movements.rs", content).unwrap();
+    emit_slider_attacks();
 }
 
 fn emit_const(name: &str, values: &[u64]) -> String {
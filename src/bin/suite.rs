@@ -0,0 +1,116 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::Write,
+    process,
+    time::{Duration, Instant},
+};
+
+use chessie::{
+    epd,
+    eval::Scorer,
+    search::{Search, SearchLimits, TranspositionTable},
+};
+
+fn usage() -> ! {
+    eprintln!("usage: chessie-suite <file.epd> [--depth N | --movetime MS] [--csv out.csv]");
+    process::exit(2);
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next() else { usage() };
+    let mut limits = SearchLimits::default();
+    let mut csv = None;
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--depth" => limits.depth = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--movetime" => {
+                let millis = value.parse().unwrap_or_else(|_| usage());
+                limits.movetime = Some(Duration::from_millis(millis));
+            }
+            "--csv" => csv = Some(value),
+            _ => usage(),
+        }
+    }
+    if limits.depth.is_none() && limits.movetime.is_none() {
+        limits.depth = Some(5);
+    }
+
+    let input = fs::read_to_string(&path).unwrap_or_else(|err| {
+        eprintln!("cannot read {path}: {err}");
+        process::exit(2);
+    });
+    let mut file = csv.map(|csv| {
+        let mut file = File::create(csv).unwrap();
+        writeln!(file, "name,duration_ms,nodes,nodes_sec,qnodes,solved").unwrap();
+        file
+    });
+
+    let (mut solved, mut scored, mut total_nodes) = (0, 0, 0);
+    let start = Instant::now();
+    for (line_no, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let epd = match epd::decode(line) {
+            Ok(epd) => epd,
+            Err(err) => {
+                eprintln!("line {}: {err}", line_no + 1);
+                continue;
+            }
+        };
+        let name = epd.id().map_or_else(|| format!("line {}", line_no + 1), str::to_string);
+
+        let mut tt = TranspositionTable::default();
+        let position_start = Instant::now();
+        let (result, stats) = Search::from_limits(&epd.board, limits.clone(), Scorer::eval)
+            .with_table(&mut tt)
+            .find_with_stats();
+        let duration = position_start.elapsed();
+        let nodes = stats.nodes + stats.qnodes;
+        total_nodes += nodes;
+
+        let check = epd.check(&result);
+        let status = match check {
+            Some(true) => "solved",
+            Some(false) => "failed",
+            None => "-",
+        };
+        if let Some(passed) = check {
+            scored += 1;
+            solved += usize::from(passed);
+        }
+        let movement = result.movement.map_or_else(|| "-".to_string(), |m| m.to_uci());
+        #[allow(
+            clippy::cast_precision_loss,
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss
+        )]
+        let nps = (nodes as f64 / duration.as_secs_f64().max(0.001)) as u64;
+        println!(
+            "{name:<30} {status:<7} {movement:<6} depth {:<3} nodes {nodes:<10} time {}ms",
+            result.depth,
+            duration.as_millis()
+        );
+
+        if let Some(file) = file.as_mut() {
+            writeln!(
+                file,
+                "\"{name}\",{},{},{nps},{},{}",
+                duration.as_millis(),
+                stats.nodes,
+                stats.qnodes,
+                u8::from(check == Some(true))
+            )
+            .unwrap();
+        }
+    }
+
+    let elapsed = start.elapsed();
+    println!();
+    println!("solved {solved}/{scored}");
+    println!("nodes {total_nodes}");
+    println!("time {}ms", elapsed.as_millis());
+}
@@ -20,13 +20,8 @@ pub(crate) const fn init(piece: Piece, color: Color) -> BitBoard {
 }
 
 #[must_use]
-pub(crate) const fn count(mut bb: BitBoard) -> usize {
-    let mut count = 0;
-    while bb != 0 {
-        count += 1;
-        bb &= bb - 1;
-    }
-    count
+pub(crate) const fn count(bb: BitBoard) -> usize {
+    bb.count_ones() as usize
 }
 
 #[must_use]
@@ -46,26 +41,36 @@ pub(crate) fn unset(bb: &mut BitBoard, sq: Sq) {
     *bb &= !pos::bb(sq);
 }
 
+/// Squares set in `bb`, from the lowest to the highest.
 #[must_use]
-#[allow(clippy::cast_possible_truncation)]
-pub(crate) fn pos(mut bb: BitBoard) -> Vec<Sq> {
-    let mut acc: Vec<Sq> = vec![];
-    let mut square: Sq = 0;
-    while bb > 0 {
-        let mut shift = bb.trailing_zeros() as u8;
-        if shift == 0 {
-            acc.push(square);
-            shift = 1;
-        }
-        bb >>= shift;
-        square += shift;
+pub(crate) const fn pos(bb: BitBoard) -> Squares {
+    Squares(bb)
+}
+
+/// Iterator over the set squares of a bitboard, see [`pos`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Squares(BitBoard);
+
+impl Iterator for Squares {
+    type Item = Sq;
+
+    fn next(&mut self) -> Option<Sq> {
+        let sq = first_pos(self.0)?;
+        self.0 &= self.0 - 1;
+        Some(sq)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = count(self.0);
+        (count, Some(count))
     }
-    acc
 }
 
+impl ExactSizeIterator for Squares {}
+
 #[must_use]
 #[allow(clippy::cast_possible_truncation)]
-pub(crate) fn first_pos(bb: BitBoard) -> Option<Sq> {
+pub(crate) const fn first_pos(bb: BitBoard) -> Option<Sq> {
     if bb == 0 {
         return None;
     }
@@ -132,7 +137,9 @@ mod test {
     fn pos() {
         let sut = 0x800c00000a007000;
         let expected: Vec<Sq> = vec![12, 13, 14, 25, 27, 50, 51, 63];
-        assert_eq!(expected, bits::pos(sut));
+        assert_eq!(expected, bits::pos(sut).collect::<Vec<_>>());
+        assert_eq!(8, bits::pos(sut).len());
+        assert_eq!(None, bits::pos(0).next());
     }
 
     #[test]
@@ -1,13 +1,19 @@
 use crate::{
     Color, bits,
-    defs::{BitBoard, CastlingUpdate, Sq},
-    moves::{self, Generator, Move},
+    defs::{BitBoard, CastlingFiles, CastlingUpdate, Sq},
+    magic::{self, MagicMovements, Masks},
+    moves::{Generator, Legality, Move, MoveList},
     piece::Piece,
+    pos,
 };
 
-pub(crate) use self::state::GameState;
+pub use self::outcome::Outcome;
+pub(crate) use self::state::{GameState, Irreversible};
 
+mod chess960;
+mod outcome;
 mod state;
+mod zobrist;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Board {
@@ -16,7 +22,13 @@ pub struct Board {
     white_side: BitBoard,
     black_side: BitBoard,
     occupancy: BitBoard,
+    /// Piece on each square, kept in sync with the bitboards so lookups by
+    /// square don't have to scan them.
+    mailbox: [Option<(Color, Piece)>; 64],
     state: GameState,
+    key: u64,
+    history: Vec<Irreversible>,
+    keys: Vec<u64>,
 }
 
 impl Board {
@@ -24,7 +36,9 @@ impl Board {
         let mut board = Self::default();
         board.white.iter_mut().for_each(|bb| *bb = 0);
         board.black.iter_mut().for_each(|bb| *bb = 0);
+        board.mailbox = [None; 64];
         board.calculate_occupancies();
+        board.recompute_key();
         board
     }
 
@@ -51,37 +65,104 @@ impl Board {
             Color::B => bits::set(&mut self.black[piece.idx()], sq),
             Color::W => bits::set(&mut self.white[piece.idx()], sq),
         }
+        self.mailbox[sq as usize] = Some((color, piece));
+        self.key ^= zobrist::piece(color, piece, sq);
     }
 
     pub(crate) fn slide(&mut self, from: Sq, to: Sq) {
-        let (_, _, bb) = self
+        let (color, piece, bb) = self
             .at_mut(from)
             .unwrap_or_else(|| unreachable!("must have a piece in order to slide {from} to {to}"));
         bits::slide(bb, from, to);
+        self.mailbox[to as usize] = self.mailbox[from as usize].take();
+        self.key ^= zobrist::piece(color, piece, from) ^ zobrist::piece(color, piece, to);
     }
 
     pub(crate) fn clear(&mut self, sq: Sq) {
-        if let Some((_, _, bb)) = self.at_mut(sq) {
+        if let Some((color, piece, bb)) = self.at_mut(sq) {
             bits::unset(bb, sq);
+            self.mailbox[sq as usize] = None;
+            self.key ^= zobrist::piece(color, piece, sq);
         }
     }
 
     pub(crate) fn disable_castling(&mut self, color: Color, update: CastlingUpdate) {
-        self.state.set_castling(color, update, false);
+        self.set_castling(color, update, false);
     }
 
     pub(crate) fn enable_castling(&mut self, color: Color, update: CastlingUpdate) {
-        self.state.set_castling(color, update, true);
+        self.set_castling(color, update, true);
+    }
+
+    fn set_castling(&mut self, color: Color, update: CastlingUpdate, value: bool) {
+        let (left, right) = self.state.set_castling(color, update, value);
+        if left {
+            self.key ^= zobrist::castling(color, true);
+        }
+        if right {
+            self.key ^= zobrist::castling(color, false);
+        }
+    }
+
+    pub(crate) fn set_castling_files(&mut self, color: Color, files: CastlingFiles) {
+        self.state.set_castling_files(color, files);
     }
 
     pub(crate) fn set_mover(&mut self, mover: Color) {
-        self.state.set_mover(mover);
+        if self.state.set_mover(mover) {
+            self.key ^= zobrist::mover();
+        }
     }
 
     pub(crate) fn set_fullmove(&mut self, fullmove: usize) {
         self.state.set_fullmove(fullmove);
     }
 
+    pub(crate) fn set_halfmove(&mut self, halfmove: usize) {
+        self.state.set_halfmove(halfmove);
+    }
+
+    pub(crate) fn set_en_passant(&mut self, en_passant: Option<Sq>) {
+        if let Some(sq) = self.state.en_passant()
+            && self.can_take_en_passant(sq)
+        {
+            self.key ^= zobrist::en_passant(sq);
+        }
+        if let Some(sq) = en_passant
+            && self.can_take_en_passant(sq)
+        {
+            self.key ^= zobrist::en_passant(sq);
+        }
+        self.state.set_en_passant(en_passant);
+    }
+
+    /// Whether a pawn stands ready to take en passant on `sq`. The square
+    /// is only hashed when one does, so a double push nobody can capture
+    /// does not keep the position from repeating.
+    #[must_use]
+    pub(crate) fn can_take_en_passant(&self, sq: Sq) -> bool {
+        let by = if pos::row(sq) == Color::W.pawn_row() + 1 { Color::B } else { Color::W };
+        self.attackers(sq, by, self.occupancy) & self.get(by, Piece::Pawn) != 0
+    }
+
+    /// Reserves room for `plies` more moves in the undo stacks, so a search
+    /// never reallocates them mid-tree.
+    pub(crate) fn reserve_plies(&mut self, plies: usize) {
+        self.history.reserve(plies);
+        self.keys.reserve(plies);
+    }
+
+    pub(crate) fn push_history(&mut self) {
+        self.history.push(self.state.irreversible());
+    }
+
+    pub(crate) fn pop_history(&mut self) {
+        let irreversible =
+            self.history.pop().unwrap_or_else(|| unreachable!("cannot unapply without history"));
+        self.set_en_passant(irreversible.en_passant);
+        self.state.restore(irreversible);
+    }
+
     #[must_use]
     pub(crate) const fn get(&self, color: Color, piece: Piece) -> BitBoard {
         match color {
@@ -102,106 +183,168 @@ impl Board {
 
     #[must_use]
     pub(crate) fn at(&self, sq: Sq) -> Option<(Color, Piece, BitBoard)> {
-        self.white
-            .into_iter()
-            .position(|bb| bits::has_piece(bb, sq))
-            .map(|i| (Color::W, Piece::from_idx(i), self.white[i]))
-            .or_else(|| {
-                self.black
-                    .into_iter()
-                    .position(|bb| bits::has_piece(bb, sq))
-                    .map(|i| (Color::B, Piece::from_idx(i), self.black[i]))
-            })
+        self.mailbox[sq as usize].map(|(color, piece)| (color, piece, self.get(color, piece)))
     }
 
     #[must_use]
     pub(crate) fn at_mut(&mut self, sq: Sq) -> Option<(Color, Piece, &mut BitBoard)> {
-        self.white
-            .into_iter()
-            .position(|bb| bits::has_piece(bb, sq))
-            .map(|i| (Color::W, Piece::from_idx(i), &mut self.white[i]))
-            .or_else(|| {
-                self.black
-                    .into_iter()
-                    .position(|bb| bits::has_piece(bb, sq))
-                    .map(|i| (Color::B, Piece::from_idx(i), &mut self.black[i]))
-            })
+        let (color, piece) = self.mailbox[sq as usize]?;
+        let bbs = match color {
+            Color::B => &mut self.black,
+            Color::W => &mut self.white,
+        };
+        Some((color, piece, &mut bbs[piece.idx()]))
     }
 
     pub(crate) fn advance(&mut self) {
         self.calculate_occupancies();
         self.state.advance();
+        self.key ^= zobrist::mover();
     }
 
     pub(crate) fn backwards(&mut self) {
         self.calculate_occupancies();
         self.state.backwards();
+        self.key ^= zobrist::mover();
+    }
+
+    #[must_use]
+    pub(crate) fn movements(&self, color: Color) -> MoveList {
+        self.generate_movements(color)
     }
 
+    /// Resolves a move in UCI long algebraic notation against the legal
+    /// moves of the side to move. Castling is accepted both as the king
+    /// moving to its target square and as the king taking its own rook.
     #[must_use]
-    pub(crate) fn movements(&self, color: Color) -> Vec<Move> {
-        self.generate_movements(color, true)
+    pub fn parse_uci_move(&self, input: &str) -> Option<Move> {
+        self.movements(self.state.mover()).into_iter().find(|movement| {
+            movement.to_uci().eq_ignore_ascii_case(input)
+                || movement.to_uci_chess960().eq_ignore_ascii_case(input)
+        })
     }
 
     #[must_use]
-    pub(crate) fn pseudo_movements(&self, color: Color) -> Vec<Move> {
-        self.generate_movements(color, false)
+    pub(crate) fn in_check(&self, color: Color) -> bool {
+        bits::first_pos(self.get(color, Piece::King))
+            .is_none_or(|king| self.is_square_attacked(king, color.flip()))
     }
 
+    /// Whether any piece of `by` attacks `sq`.
     #[must_use]
-    pub(crate) fn count_pieces(&self) -> usize {
-        self.pieces(Color::W)
-            .chain(self.pieces(Color::B))
-            .filter(|(p, _)| *p != Piece::Pawn)
-            .map(|(_, bb)| bits::count(bb))
-            .sum()
+    pub(crate) fn is_square_attacked(&self, sq: Sq, by: Color) -> bool {
+        self.attackers(sq, by, self.occupancy) != 0
     }
 
+    /// Pieces of `by` attacking `sq`, with sliders blocked by `occupancy`.
+    /// Looks from the target square outwards: a knight, king, pawn or slider
+    /// attacks it if it stands on a square the same kind of piece on `sq`
+    /// would attack.
     #[must_use]
-    pub(crate) fn in_check(&self, color: Color) -> bool {
-        if let Some(pos) = bits::first_pos(self.get(color, Piece::King)) {
-            let moves = self.pseudo_movements(color.flip());
-            moves::is_attacked(&moves, pos)
-        } else {
-            true
-        }
+    pub(crate) fn attackers(&self, sq: Sq, by: Color, occupancy: BitBoard) -> BitBoard {
+        let target = pos::bb(sq);
+        let pawns = match by {
+            Color::B => {
+                (bits::northeast(target) & Masks::NOT_FILE_A)
+                    | (bits::northwest(target) & Masks::NOT_FILE_H)
+            }
+            Color::W => {
+                (bits::southeast(target) & Masks::NOT_FILE_A)
+                    | (bits::southwest(target) & Masks::NOT_FILE_H)
+            }
+        };
+        let queens = self.get(by, Piece::Queen);
+
+        (pawns & self.get(by, Piece::Pawn))
+            | (MagicMovements::KNIGHT_MOVES[sq as usize] & self.get(by, Piece::Knight))
+            | (MagicMovements::KING_MOVES[sq as usize] & self.get(by, Piece::King))
+            | (magic::rook_attacks(sq, occupancy) & (self.get(by, Piece::Rook) | queens))
+            | (magic::bishop_attacks(sq, occupancy) & (self.get(by, Piece::Bishop) | queens))
     }
 
     pub(crate) fn calculate_occupancies(&mut self) {
         self.white_side = collapse(self.white);
         self.black_side = collapse(self.black);
         self.occupancy = self.white_side | self.black_side;
+        debug_assert_eq!(
+            mailbox(&self.white, &self.black),
+            self.mailbox,
+            "mailbox out of sync with the bitboards"
+        );
+    }
+
+    /// Zobrist key of the position: piece placement, side to move, castling
+    /// rights and en passant square. Kept up to date as moves are applied.
+    #[must_use]
+    pub(crate) const fn key(&self) -> u64 {
+        self.key
+    }
+
+    pub(crate) fn recompute_key(&mut self) {
+        self.key = zobrist::compute(self);
+    }
+
+    /// Whether the current position has been reached at least `count` times,
+    /// counting the current occurrence. Only positions since the last capture
+    /// or pawn move are considered, as earlier ones can no longer repeat.
+    #[must_use]
+    pub(crate) fn is_repetition(&self, count: usize) -> bool {
+        let key = self.key();
+        let seen = self
+            .keys
+            .iter()
+            .rev()
+            .take(self.state.halfmove())
+            .skip(1)
+            .step_by(2)
+            .filter(|k| **k == key)
+            .count();
+        seen + 1 >= count
     }
 
     pub(crate) fn apply_mut(&mut self, movement: Move) {
+        self.keys.push(self.key);
         movement.apply(self);
         self.advance();
+        debug_assert_eq!(zobrist::compute(self), self.key, "key out of sync after {movement}");
     }
 
     pub(crate) fn unapply_mut(&mut self, movement: Move) {
         movement.unapply(self);
         self.backwards();
+        self.keys.pop();
+        debug_assert_eq!(zobrist::compute(self), self.key, "key out of sync after undo {movement}");
     }
 
-    fn generate_movements(&self, color: Color, legal_only: bool) -> Vec<Move> {
-        self.pieces(color)
-            .flat_map(|(_, bb)| bits::pos(bb))
-            .flat_map(|p| Generator::from_board(self, p, legal_only).generate())
-            .collect()
+    fn generate_movements(&self, color: Color) -> MoveList {
+        let legality = Legality::new(self, color);
+        let mut movements = MoveList::new();
+        for (_, bb) in self.pieces(color) {
+            for from in bits::pos(bb) {
+                Generator::from_board(self, from, &legality).generate(&mut movements);
+            }
+        }
+        movements
     }
 }
 
 impl Default for Board {
     fn default() -> Self {
+        let (white, black) = (init_pieces(Color::W), init_pieces(Color::B));
         let mut board = Self {
-            white: init_pieces(Color::W),
-            black: init_pieces(Color::B),
+            white,
+            black,
+            mailbox: mailbox(&white, &black),
             state: GameState::default(),
+            key: 0,
+            history: Vec::new(),
+            keys: Vec::new(),
             white_side: 0,
             black_side: 0,
             occupancy: 0,
         };
         board.calculate_occupancies();
+        board.recompute_key();
         board
     }
 }
@@ -217,6 +360,19 @@ const fn init_pieces(color: Color) -> [BitBoard; 6] {
     ]
 }
 
+/// Piece on each square according to the bitboards.
+fn mailbox(white: &[BitBoard; 6], black: &[BitBoard; 6]) -> [Option<(Color, Piece)>; 64] {
+    let mut mailbox = [None; 64];
+    for (color, bbs) in [(Color::W, white), (Color::B, black)] {
+        for (i, bb) in bbs.iter().enumerate() {
+            for sq in bits::pos(*bb) {
+                mailbox[sq as usize] = Some((color, Piece::from_idx(i)));
+            }
+        }
+    }
+    mailbox
+}
+
 const fn collapse(bbs: [BitBoard; 6]) -> BitBoard {
     bbs[0] | bbs[1] | bbs[2] | bbs[3] | bbs[4] | bbs[5]
 }
@@ -226,7 +382,9 @@ mod test {
 
     use std::mem;
 
-    use crate::squares::*;
+    use test_case::test_case;
+
+    use crate::{fen, squares::*};
 
     use super::*;
 
@@ -270,14 +428,162 @@ mod test {
     }
 
     #[test]
-    fn count_pieces() {
-        let sut = Board::default();
-        assert_eq!(16, sut.count_pieces());
+    fn mailbox_follows_moves() {
+        let mut sut =
+            fen::decode("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let original = sut.clone();
+        let mut played = Vec::new();
+        for uci in ["e1c1", "h3g2", "d5e6", "g2h1q", "e6f7"] {
+            let movement = sut.parse_uci_move(uci).unwrap();
+            sut.apply_mut(movement);
+            played.push(movement);
+            assert_eq!(mailbox(&sut.white, &sut.black), sut.mailbox);
+        }
+        assert_eq!(Some((Color::W, Piece::King, sut.get(Color::W, Piece::King))), sut.at(C1));
+        assert_eq!(Some((Color::B, Piece::Queen, sut.get(Color::B, Piece::Queen))), sut.at(H1));
+        assert_eq!(None, sut.at(E1));
+
+        for movement in played.into_iter().rev() {
+            sut.unapply_mut(movement);
+        }
+        assert_eq!(original, sut);
+    }
+
+    #[test]
+    fn key_matches_decoded_position() {
+        let decoded =
+            fen::decode("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert_eq!(Board::default().key(), decoded.key());
+    }
+
+    #[test]
+    fn key_is_restored_on_unapply() {
+        let mut sut = fen::decode("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
+        let original = sut.key();
+        let movement = Move::LeftCastle {
+            mover: Color::W,
+            king: E1,
+            rook: A1,
+            castling_update: CastlingUpdate::Both,
+        };
+
+        sut.apply_mut(movement);
+        assert_ne!(original, sut.key());
+
+        sut.unapply_mut(movement);
+        assert_eq!(original, sut.key());
+    }
+
+    #[test]
+    fn key_transposition() {
+        let mut a = Board::default();
+        let mut b = Board::default();
+        let (nf3, nc3) = (
+            Move::Slide { from: G1, to: F3, castling_update: None },
+            Move::Slide { from: B1, to: C3, castling_update: None },
+        );
+        let nf6 = Move::Slide { from: G8, to: F6, castling_update: None };
+
+        [nf3, nf6, nc3].into_iter().for_each(|m| a.apply_mut(m));
+        [nc3, nf6, nf3].into_iter().for_each(|m| b.apply_mut(m));
+        assert_eq!(a.key(), b.key());
+
+        a.apply_mut(Move::Slide { from: E7, to: E5, castling_update: None });
+        b.apply_mut(Move::Slide { from: E7, to: E6, castling_update: None });
+        b.apply_mut(Move::Slide { from: A1, to: B1, castling_update: None });
+        assert_ne!(a.key(), b.key());
+    }
+
+    #[test]
+    fn is_repetition() {
+        let shuffle = [
+            Move::Slide { from: G1, to: F3, castling_update: None },
+            Move::Slide { from: G8, to: F6, castling_update: None },
+            Move::Slide { from: F3, to: G1, castling_update: None },
+            Move::Slide { from: F6, to: G8, castling_update: None },
+        ];
+        let mut sut = Board::default();
+        assert!(sut.is_repetition(1));
+        assert!(!sut.is_repetition(2));
+
+        shuffle.into_iter().for_each(|m| sut.apply_mut(m));
+        assert!(sut.is_repetition(2));
+        assert!(!sut.is_repetition(3));
+
+        shuffle.into_iter().for_each(|m| sut.apply_mut(m));
+        assert!(sut.is_repetition(3));
+
+        sut.unapply_mut(shuffle[3]);
+        assert!(sut.is_repetition(2));
+        assert!(!sut.is_repetition(3));
+    }
+
+    #[test]
+    fn is_repetition_stops_at_irreversible_move() {
+        let mut sut = Board::default();
+        sut.apply_mut(Move::Slide { from: G1, to: F3, castling_update: None });
+        sut.apply_mut(Move::Slide { from: G8, to: F6, castling_update: None });
+        sut.apply_mut(Move::Slide { from: F3, to: G1, castling_update: None });
+        sut.apply_mut(Move::Slide { from: F6, to: G8, castling_update: None });
+        sut.set_halfmove(0);
+
+        assert!(!sut.is_repetition(2));
+    }
+
+    #[test]
+    fn parse_uci_move() {
+        let sut = fen::decode("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+
+        assert!(matches!(sut.parse_uci_move("e1g1"), Some(Move::RightCastle { .. })));
+        assert!(matches!(sut.parse_uci_move("e1c1"), Some(Move::LeftCastle { .. })));
+        assert!(matches!(sut.parse_uci_move("e5d6"), Some(Move::EnPassant { from: E5, to: D6 })));
+        assert!(matches!(
+            sut.parse_uci_move("b7a8n"),
+            Some(Move::PawnPromo {
+                promo_piece: Piece::Knight,
+                taken_piece: Some(Piece::Rook),
+                ..
+            })
+        ));
+        assert!(matches!(sut.parse_uci_move("a1a8"), Some(Move::Takes { piece: Piece::Rook, .. })));
+        assert!(matches!(sut.parse_uci_move("e5e6"), Some(Move::Slide { .. })));
+
+        assert_eq!(None, sut.parse_uci_move("b7b8"));
+        assert_eq!(None, sut.parse_uci_move("e1e3"));
+        assert_eq!(None, sut.parse_uci_move("e8g8"));
+        assert_eq!(None, sut.parse_uci_move("xyz"));
+    }
+
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - 0 1", E2, Color::W, true; "king")]
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - 0 1", E3, Color::W, false; "king out of reach")]
+    #[test_case("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1", E3, Color::W, true; "white pawn")]
+    #[test_case("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1", D3, Color::W, false; "white pawn push")]
+    #[test_case("4k3/8/8/8/8/8/P7/4K3 w - - 0 1", H3, Color::W, false; "white pawn wrap")]
+    #[test_case("4k3/3p4/8/8/8/8/8/4K3 w - - 0 1", C6, Color::B, true; "black pawn")]
+    #[test_case("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", C3, Color::W, true; "knight")]
+    #[test_case("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", A8, Color::W, true; "rook")]
+    #[test_case("4k3/8/8/8/p7/8/8/R3K3 w - - 0 1", A8, Color::W, false; "rook blocked")]
+    #[test_case("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", H6, Color::W, true; "bishop")]
+    #[test_case("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", H5, Color::W, true; "queen diagonal")]
+    #[test_case("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", D8, Color::W, true; "queen file")]
+    #[test_case("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", D8, Color::B, true; "black king")]
+    #[test_case("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", C8, Color::W, false; "not attacked")]
+    fn is_square_attacked(input: &str, sq: Sq, by: Color, expected: bool) {
+        let board = fen::decode(input).unwrap();
+        assert_eq!(expected, board.is_square_attacked(sq, by));
+    }
+
+    #[test]
+    fn parse_uci_move_king_takes_rook() {
+        let sut = fen::decode("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert!(matches!(sut.parse_uci_move("e1h1"), Some(Move::RightCastle { .. })));
+        assert!(matches!(sut.parse_uci_move("e1a1"), Some(Move::LeftCastle { .. })));
     }
 
     #[test]
     fn size() {
-        assert_eq!(136, mem::size_of::<Board>());
+        assert_eq!(336, mem::size_of::<Board>());
         assert_eq!(8, mem::size_of::<&Board>());
     }
 }
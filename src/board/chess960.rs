@@ -0,0 +1,98 @@
+use crate::{Color, defs::CastlingUpdate, piece::Piece};
+
+use super::Board;
+
+/// Knight placements over the five squares left once the bishops and the
+/// queen are placed, in Scharnagl order.
+const KNIGHTS: [(usize, usize); 10] =
+    [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+impl Board {
+    /// Chess960 start position number `index`, from 0 to 959, using the
+    /// Scharnagl numbering where 518 is the standard setup. Indices out of
+    /// range wrap around.
+    #[must_use]
+    pub fn chess960(index: usize) -> Self {
+        let rank = chess960_rank(index % 960);
+        let mut board = Board::empty();
+        for color in [Color::W, Color::B] {
+            let (row, pawn_row) = (color.piece_row() * 8, color.pawn_row() * 8);
+            for (file, piece) in (0..8).zip(rank) {
+                board.add(color, piece, row + file);
+                board.add(color, Piece::Pawn, pawn_row + file);
+            }
+            let mut rooks = (0..8).filter(|file| rank[*file as usize] == Piece::Rook);
+            let (left, right) = (rooks.next().unwrap_or(0), rooks.next().unwrap_or(7));
+            board.set_castling_files(color, (left, right));
+            board.enable_castling(color, CastlingUpdate::Both);
+        }
+        board.calculate_occupancies();
+        board.recompute_key();
+        board
+    }
+}
+
+fn chess960_rank(index: usize) -> [Piece; 8] {
+    let mut rank = [None; 8];
+    let (light, index) = (index % 4, index / 4);
+    let (dark, index) = (index % 4, index / 4);
+    let (queen, knights) = (index % 6, index / 6);
+    rank[light * 2 + 1] = Some(Piece::Bishop);
+    rank[dark * 2] = Some(Piece::Bishop);
+
+    let empty = |rank: &[Option<Piece>; 8]| -> Vec<usize> {
+        (0..8).filter(|file| rank[*file].is_none()).collect()
+    };
+    rank[empty(&rank)[queen]] = Some(Piece::Queen);
+    let free = empty(&rank);
+    let (first, second) = KNIGHTS[knights];
+    rank[free[first]] = Some(Piece::Knight);
+    rank[free[second]] = Some(Piece::Knight);
+
+    // The king always stands between the rooks.
+    for (file, piece) in empty(&rank).into_iter().zip([Piece::Rook, Piece::King, Piece::Rook]) {
+        rank[file] = Some(piece);
+    }
+    rank.map(|piece| piece.unwrap_or(Piece::Pawn))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{bits, fen, magic::Masks, perft::perft};
+
+    #[test_case(518, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")]
+    #[test_case(0, "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1")]
+    #[test_case(959, "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1")]
+    fn start_position(index: usize, expected: &str) {
+        assert_eq!(expected, fen::encode(&Board::chess960(index)));
+    }
+
+    #[test]
+    fn start_positions_are_distinct_and_valid() {
+        let positions: HashSet<_> = (0..960).map(|i| fen::encode(&Board::chess960(i))).collect();
+        assert_eq!(960, positions.len());
+
+        for index in 0..960 {
+            let board = Board::chess960(index);
+            let bishops = board.get(Color::W, Piece::Bishop);
+            assert_eq!(1, bits::count(bishops & Masks::DARK_SQUARES));
+            let (left, right) = board.state().castling_rooks(Color::W);
+            let king = bits::first_pos(board.get(Color::W, Piece::King)).unwrap();
+            assert!(left < king && king < right);
+        }
+    }
+
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 3, 12_189)]
+    #[test_case("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", 3, 18_002)]
+    #[test_case("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", 3, 10_471)]
+    #[test_case("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", 3, 31_058)]
+    fn perft_reference(input: &str, depth: usize, expected: u64) {
+        let board = fen::decode(input).unwrap();
+        assert_eq!(expected, perft(&board, depth));
+    }
+}
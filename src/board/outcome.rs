@@ -0,0 +1,124 @@
+use crate::{Color, bits, magic::Masks, piece::Piece};
+
+use super::Board;
+
+/// Why a game is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Checkmate { winner: Color },
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoveRule,
+    Repetition,
+}
+
+impl Outcome {
+    /// The winning side, `None` for draws.
+    #[must_use]
+    pub const fn winner(self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate { winner } => Some(winner),
+            _ => None,
+        }
+    }
+}
+
+impl Board {
+    /// Whether the game is over in the current position. Draws by the
+    /// fifty-move rule and threefold repetition are treated as claimed.
+    #[must_use]
+    pub fn outcome(&self) -> Option<Outcome> {
+        let mover = self.state.mover();
+        if self.movements(mover).is_empty() {
+            return Some(if self.in_check(mover) {
+                Outcome::Checkmate { winner: mover.flip() }
+            } else {
+                Outcome::Stalemate
+            });
+        }
+        if self.is_insufficient_material() {
+            Some(Outcome::InsufficientMaterial)
+        } else if self.state.is_fifty_move_draw() {
+            Some(Outcome::FiftyMoveRule)
+        } else if self.is_repetition(3) {
+            Some(Outcome::Repetition)
+        } else {
+            None
+        }
+    }
+
+    /// Neither side can mate with the remaining material: bare kings, a
+    /// single minor piece, or only bishops that all stand on squares of
+    /// the same color.
+    #[must_use]
+    pub(crate) fn is_insufficient_material(&self) -> bool {
+        let heavy = [Piece::Pawn, Piece::Rook, Piece::Queen];
+        if [Color::W, Color::B]
+            .into_iter()
+            .any(|color| heavy.iter().any(|p| self.get(color, *p) != 0))
+        {
+            return false;
+        }
+
+        let knights = self.get(Color::W, Piece::Knight) | self.get(Color::B, Piece::Knight);
+        let bishops = self.get(Color::W, Piece::Bishop) | self.get(Color::B, Piece::Bishop);
+        match (bits::count(knights), bits::count(bishops)) {
+            (0, 0) | (1, 0) | (0, 1) => true,
+            (0, _) => bishops & Masks::DARK_SQUARES == 0 || bishops & Masks::LIGHT_SQUARES == 0,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{fen, pgn};
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", None)]
+    #[test_case("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", Some(Outcome::Stalemate))]
+    #[test_case("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1", Some(Outcome::Checkmate { winner: Color::W }))]
+    #[test_case("8/8/8/8/8/2k5/1q6/K7 w - - 0 1", Some(Outcome::Checkmate { winner: Color::B }))]
+    #[test_case("8/8/4k3/8/8/8/8/4K3 w - - 0 1", Some(Outcome::InsufficientMaterial))]
+    #[test_case("8/8/4k3/8/8/8/8/4KN2 w - - 0 1", Some(Outcome::InsufficientMaterial))]
+    #[test_case("8/8/4k3/8/8/8/8/4KB2 b - - 0 1", Some(Outcome::InsufficientMaterial))]
+    #[test_case("8/8/4kb2/8/8/8/8/2B1K3 w - - 0 1", Some(Outcome::InsufficientMaterial))]
+    #[test_case("8/8/4k1b1/8/8/8/8/2B1K3 w - - 0 1", None)]
+    #[test_case("8/8/4k3/8/8/8/8/3NKN2 w - - 0 1", None)]
+    #[test_case("8/8/4kn2/8/8/8/8/4KB2 w - - 0 1", None)]
+    #[test_case("8/8/4k3/8/8/8/8/4KR2 w - - 0 1", None)]
+    #[test_case("8/8/4k3/8/8/8/8/R3K3 w - - 100 80", Some(Outcome::FiftyMoveRule))]
+    fn outcome(input: &str, expected: Option<Outcome>) {
+        let board = fen::decode(input).unwrap();
+        assert_eq!(expected, board.outcome());
+    }
+
+    #[test]
+    fn outcome_repetition() {
+        let mut board = fen::decode("8/8/4k3/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        for _ in 0..2 {
+            for uci in ["a1a2", "e6e7", "a2a1", "e7e6"] {
+                assert_eq!(None, board.outcome());
+                board.apply_mut(board.parse_uci_move(uci).unwrap());
+            }
+        }
+        assert_eq!(Some(Outcome::Repetition), board.outcome());
+    }
+
+    #[test]
+    fn outcome_repetition_after_double_push() {
+        // Nothing can take on e3 or e6, so the position after 1...e5 repeats
+        // for the third time at ply 10.
+        let game = pgn::decode(
+            "1. e4 e5 2. Nf3 Nc6 3. Ng1 Nb8 4. Nf3 Nc6 5. Ng1 Nb8 6. Nf3 Nc6 7. Ng1 Nb8 *",
+        )
+        .next()
+        .unwrap()
+        .unwrap();
+        let repeated =
+            game.positions().position(|board| board.outcome() == Some(Outcome::Repetition));
+        assert_eq!(Some(10), repeated);
+    }
+}
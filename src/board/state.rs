@@ -1,14 +1,26 @@
 use crate::{
     color::Color,
-    defs::{CastlingRights, CastlingUpdate},
+    defs::{CastlingFiles, CastlingRights, CastlingUpdate, Sq},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct GameState {
     mover: Color,
     fullmove: usize,
+    halfmove: usize,
     white_castling: CastlingRights,
     black_castling: CastlingRights,
+    white_rook_files: CastlingFiles,
+    black_rook_files: CastlingFiles,
+    en_passant: Option<Sq>,
+}
+
+/// Parts of the state that cannot be derived back from a move, saved before
+/// applying it so they can be restored when the move is unapplied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Irreversible {
+    pub(crate) en_passant: Option<Sq>,
+    pub(crate) halfmove: usize,
 }
 
 impl GameState {
@@ -17,11 +29,41 @@ impl GameState {
         self.fullmove
     }
 
+    /// Plies since the last capture or pawn move.
+    #[must_use]
+    pub(crate) const fn halfmove(&self) -> usize {
+        self.halfmove
+    }
+
+    /// A hundred plies without captures or pawn moves, which is the point at
+    /// which the fifty-move rule allows claiming a draw.
+    #[must_use]
+    pub(crate) const fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove >= 100
+    }
+
     #[must_use]
     pub(crate) const fn mover(&self) -> Color {
         self.mover
     }
 
+    /// Square a pawn skipped over with a double push on the previous move,
+    /// which is where an en passant capture would land.
+    #[must_use]
+    pub(crate) const fn en_passant(&self) -> Option<Sq> {
+        self.en_passant
+    }
+
+    #[must_use]
+    pub(crate) const fn irreversible(&self) -> Irreversible {
+        Irreversible { en_passant: self.en_passant, halfmove: self.halfmove }
+    }
+
+    pub(crate) fn restore(&mut self, irreversible: Irreversible) {
+        self.en_passant = irreversible.en_passant;
+        self.halfmove = irreversible.halfmove;
+    }
+
     #[must_use]
     pub(crate) const fn castling_rights(&self, color: Color) -> CastlingRights {
         match color {
@@ -30,6 +72,26 @@ impl GameState {
         }
     }
 
+    /// Squares the castling rooks of `color` start on, as `(left, right)`.
+    /// These are the corners unless the game started from a Chess960
+    /// position.
+    #[must_use]
+    pub(crate) const fn castling_rooks(&self, color: Color) -> (Sq, Sq) {
+        let (left, right) = match color {
+            Color::B => self.black_rook_files,
+            Color::W => self.white_rook_files,
+        };
+        let row = color.piece_row() * 8;
+        (row + left, row + right)
+    }
+
+    pub(crate) fn set_castling_files(&mut self, color: Color, files: CastlingFiles) {
+        match color {
+            Color::B => self.black_rook_files = files,
+            Color::W => self.white_rook_files = files,
+        }
+    }
+
     pub(super) fn advance(&mut self) {
         if self.mover == Color::B {
             self.fullmove += 1;
@@ -57,6 +119,14 @@ impl GameState {
         self.fullmove = fullmove;
     }
 
+    pub(crate) fn set_halfmove(&mut self, halfmove: usize) {
+        self.halfmove = halfmove;
+    }
+
+    pub(crate) fn set_en_passant(&mut self, en_passant: Option<Sq>) {
+        self.en_passant = en_passant;
+    }
+
     /// Sets the given side(s) of `color` to `value` and returns which sides
     /// actually changed, as a `(left, right)` tuple.
     pub(crate) fn set_castling(
@@ -86,8 +156,12 @@ impl Default for GameState {
         Self {
             mover: Color::W,
             fullmove: 1,
+            halfmove: 0,
             white_castling: (true, true),
             black_castling: (true, true),
+            white_rook_files: (0, 7),
+            black_rook_files: (0, 7),
+            en_passant: None,
         }
     }
 }
@@ -95,6 +169,7 @@ impl Default for GameState {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::squares::*;
     use test_case::test_case;
 
     #[test]
@@ -150,6 +225,17 @@ mod test {
         assert_eq!(expected, sut.castling_rights(color));
     }
 
+    #[test]
+    fn castling_rooks() {
+        let mut sut = GameState::default();
+        assert_eq!((A1, H1), sut.castling_rooks(Color::W));
+        assert_eq!((A8, H8), sut.castling_rooks(Color::B));
+
+        sut.set_castling_files(Color::B, (1, 6));
+        assert_eq!((B8, G8), sut.castling_rooks(Color::B));
+        assert_eq!((A1, H1), sut.castling_rooks(Color::W));
+    }
+
     #[test]
     fn set_mover_reports_changed() {
         let mut sut = GameState::default();
@@ -157,6 +243,31 @@ mod test {
         assert!(sut.set_mover(Color::B));
     }
 
+    #[test]
+    fn restore_irreversible() {
+        let mut sut = GameState::default();
+        let saved = sut.irreversible();
+
+        sut.set_en_passant(Some(20));
+        sut.set_halfmove(7);
+        assert_eq!(Some(20), sut.en_passant());
+        assert_eq!(7, sut.halfmove());
+
+        sut.restore(saved);
+        assert_eq!(None, sut.en_passant());
+        assert_eq!(0, sut.halfmove());
+    }
+
+    #[test]
+    fn fifty_move_draw() {
+        let mut sut = GameState::default();
+        sut.set_halfmove(99);
+        assert!(!sut.is_fifty_move_draw());
+
+        sut.set_halfmove(100);
+        assert!(sut.is_fifty_move_draw());
+    }
+
     #[test]
     fn set_castling_reports_changed_sides() {
         let mut sut = GameState::default();
@@ -0,0 +1,142 @@
+use crate::{
+    bits,
+    board::{Board, GameState},
+    color::Color,
+    defs::Sq,
+    piece::Piece,
+    pos,
+};
+
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+const PIECES: [[u64; 64]; 12] = {
+    let mut table = [[0; 64]; 12];
+    let mut state = SEED;
+    let mut i = 0;
+    while i < 12 {
+        let mut sq = 0;
+        while sq < 64 {
+            let (next, value) = splitmix(state);
+            table[i][sq] = value;
+            state = next;
+            sq += 1;
+        }
+        i += 1;
+    }
+    table
+};
+
+const CASTLING: [u64; 4] = fill(splitmix(SEED ^ 0xc0ff_ee00).0);
+
+const EN_PASSANT: [u64; 8] = fill(splitmix(SEED ^ 0x0e9a_5500).0);
+
+const MOVER: u64 = splitmix(SEED ^ 0x5eed_b1ac).1;
+
+/// Key of `piece` of `color` standing on `sq`.
+#[must_use]
+pub(crate) const fn piece(color: Color, piece: Piece, sq: Sq) -> u64 {
+    let offset = match color {
+        Color::B => 6,
+        Color::W => 0,
+    };
+    PIECES[offset + piece.idx()][sq as usize]
+}
+
+/// Key of a single castling right, `left` selecting the queen side.
+#[must_use]
+pub(crate) const fn castling(color: Color, left: bool) -> u64 {
+    let offset = match color {
+        Color::B => 2,
+        Color::W => 0,
+    };
+    CASTLING[offset + if left { 0 } else { 1 }]
+}
+
+/// Key of the en passant square, which only depends on its file.
+#[must_use]
+pub(crate) const fn en_passant(sq: Sq) -> u64 {
+    EN_PASSANT[pos::col(sq) as usize]
+}
+
+/// Toggled whenever the side to move changes; present when Black moves.
+#[must_use]
+pub(crate) const fn mover() -> u64 {
+    MOVER
+}
+
+/// Computes the key of `board` from scratch.
+#[must_use]
+pub(crate) fn compute(board: &Board) -> u64 {
+    let pieces = [Color::W, Color::B].into_iter().flat_map(|color| {
+        board
+            .pieces(color)
+            .flat_map(move |(p, bb)| bits::pos(bb).map(move |sq| piece(color, p, sq)))
+    });
+    let key = pieces.fold(compute_state(board.state()), |key, k| key ^ k);
+    match board.state().en_passant() {
+        Some(sq) if board.can_take_en_passant(sq) => key ^ en_passant(sq),
+        _ => key,
+    }
+}
+
+fn compute_state(state: &GameState) -> u64 {
+    let mut key = 0;
+    for color in [Color::W, Color::B] {
+        let (left, right) = state.castling_rights(color);
+        if left {
+            key ^= castling(color, true);
+        }
+        if right {
+            key ^= castling(color, false);
+        }
+    }
+    if state.mover() == Color::B {
+        key ^= mover();
+    }
+    key
+}
+
+const fn fill<const N: usize>(mut state: u64) -> [u64; N] {
+    let mut table = [0; N];
+    let mut i = 0;
+    while i < N {
+        let (next, value) = splitmix(state);
+        table[i] = value;
+        state = next;
+        i += 1;
+    }
+    table
+}
+
+/// SplitMix64 step, returning the advanced state and the generated value.
+const fn splitmix(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn keys_are_unique() {
+        let mut seen = HashSet::new();
+        let all = PIECES.iter().flatten().chain(&CASTLING).chain(&EN_PASSANT).chain([&MOVER]);
+        for key in all {
+            assert!(seen.insert(*key), "duplicated key {key:x}");
+        }
+    }
+
+    #[test]
+    fn mover_toggles_key() {
+        let mut state = GameState::default();
+        let white = compute_state(&state);
+        state.set_mover(Color::B);
+        assert_eq!(white ^ mover(), compute_state(&state));
+    }
+}
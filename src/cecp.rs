@@ -0,0 +1,429 @@
+use std::{
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+
+use crate::{
+    board::{Board, Outcome},
+    color::Color,
+    eval::Scorer,
+    fen,
+    moves::Move,
+    san,
+    search::{Clock, Search, SearchLimits, SearchResult, Stats, TimeManager, TranspositionTable},
+};
+
+/// Score reported for a mate, offset by the moves it takes to deliver it.
+const MATE_REPORT: i32 = 100_000;
+
+/// Time per move when the GUI sets no time control or depth, so `go` does
+/// not search to the maximum depth while commands wait.
+const DEFAULT_MOVETIME: Duration = Duration::from_secs(1);
+
+/// Speaks the Chess Engine Communication Protocol (xboard/winboard) over
+/// stdin and stdout until `quit`. Searches run on the calling thread, so
+/// commands sent while the engine thinks are handled once it has moved.
+pub fn run() {
+    let mut cecp = Cecp::new(io::stdout());
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !cecp.handle(&line) {
+            break;
+        }
+    }
+}
+
+/// Time control set with `level`, `st` and `sd`, and the clocks reported
+/// with `time` and `otim`.
+#[derive(Debug, Clone, Copy, Default)]
+struct TimeControl {
+    /// Moves per session, zero for sudden death.
+    moves: u32,
+    base: Duration,
+    increment: Duration,
+    /// Fixed time per move, which takes precedence over the session clock.
+    per_move: Option<Duration>,
+    depth: Option<usize>,
+    remaining: Option<Duration>,
+}
+
+impl TimeControl {
+    fn limits(&self, board: &Board) -> (SearchLimits, Option<TimeManager>) {
+        let limits =
+            SearchLimits { depth: self.depth, movetime: self.per_move, ..SearchLimits::default() };
+        if self.per_move.is_some() {
+            return (limits, None);
+        }
+        if self.base.is_zero() && self.remaining.is_none() {
+            let movetime = self.depth.is_none().then_some(DEFAULT_MOVETIME);
+            return (SearchLimits { movetime, ..limits }, None);
+        }
+
+        let remaining = self.remaining.unwrap_or(self.base);
+        let moves_to_go = (self.moves > 0).then(|| {
+            let played = u32::try_from(board.state().fullmove() - 1).unwrap_or(0);
+            self.moves - played % self.moves
+        });
+        let clock = Clock { moves_to_go, ..Clock::new(remaining, self.increment) };
+        (limits, Some(TimeManager::new(&clock, board.state().mover())))
+    }
+}
+
+pub(crate) struct Cecp<W: Write> {
+    board: Board,
+    played: Vec<Move>,
+    /// Side the engine plays, `None` in force mode.
+    engine: Option<Color>,
+    post: bool,
+    /// Playing `variant fischerandom`, where castling is sent as O-O.
+    chess960: bool,
+    time: TimeControl,
+    tt: TranspositionTable,
+    out: W,
+}
+
+impl<W: Write> Cecp<W> {
+    pub(crate) fn new(out: W) -> Self {
+        Self {
+            board: Board::default(),
+            played: Vec::new(),
+            engine: Some(Color::B),
+            post: false,
+            chess960: false,
+            time: TimeControl::default(),
+            tt: TranspositionTable::default(),
+            out,
+        }
+    }
+
+    /// Handles a single command, returning false once the engine must quit.
+    pub(crate) fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else {
+            return true;
+        };
+        let args: Vec<_> = tokens.collect();
+
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "result" | "?" => {}
+            "protover" => self.send(
+                "feature myname=\"chessie\" setboard=1 usermove=1 ping=1 playother=1 \
+                 sigint=0 sigterm=0 colors=0 analyze=0 variants=\"normal,fischerandom\" done=1",
+            ),
+            "ping" => self.send(&format!("pong {}", args.join(" "))),
+            "new" => {
+                self.set_board(Board::default());
+                self.tt.clear();
+                self.engine = Some(Color::B);
+                self.chess960 = false;
+                self.time.depth = None;
+                self.time.remaining = None;
+            }
+            "setboard" => match fen::decode(&args.join(" ")) {
+                Ok(board) => self.set_board(board),
+                Err(_) => self.send("tellusererror Illegal position"),
+            },
+            "variant" => self.chess960 = args.first() == Some(&"fischerandom"),
+            "force" => self.engine = None,
+            "go" => {
+                self.engine = Some(self.board.state().mover());
+                self.think();
+            }
+            "playother" => self.engine = Some(self.board.state().mover().flip()),
+            "usermove" => self.user_move(args.first().copied().unwrap_or_default()),
+            "undo" => self.undo(1),
+            "remove" => self.undo(2),
+            "level" => self.level(&args),
+            "st" => {
+                self.time.per_move = args
+                    .first()
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+            }
+            "sd" => self.time.depth = args.first().and_then(|d| d.parse().ok()),
+            "time" => {
+                self.time.remaining = args
+                    .first()
+                    .and_then(|cs| cs.parse::<u64>().ok())
+                    .map(|cs| Duration::from_millis(cs * 10));
+            }
+            "otim" => {}
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "quit" => return false,
+            _ if self.parse_move(command).is_some() => self.user_move(command),
+            _ => self.send(&format!("Error (unknown command): {command}")),
+        }
+        true
+    }
+
+    fn set_board(&mut self, board: Board) {
+        self.board = board;
+        self.played.clear();
+    }
+
+    /// `level MPS BASE INC`, where the base is given in minutes or as
+    /// `minutes:seconds` and the increment in seconds.
+    fn level(&mut self, args: &[&str]) {
+        let [moves, base, increment] = args else {
+            self.send("Error (bad arguments): level");
+            return;
+        };
+        let base = match base.split_once(':') {
+            Some((minutes, seconds)) => {
+                minutes.parse::<u64>().unwrap_or(0) * 60 + seconds.parse::<u64>().unwrap_or(0)
+            }
+            None => base.parse::<u64>().unwrap_or(0) * 60,
+        };
+        self.time = TimeControl {
+            moves: moves.parse().unwrap_or(0),
+            base: Duration::from_secs(base),
+            increment: increment
+                .parse::<f64>()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .unwrap_or_default(),
+            per_move: None,
+            ..self.time
+        };
+    }
+
+    /// Coordinate notation, or O-O and O-O-O for castling in Chess960.
+    fn parse_move(&self, text: &str) -> Option<Move> {
+        self.board.parse_uci_move(text).or_else(|| {
+            san::decode(&self.board, text)
+                .ok()
+                .filter(|m| matches!(m, Move::LeftCastle { .. } | Move::RightCastle { .. }))
+        })
+    }
+
+    fn move_text(&self, movement: Move) -> String {
+        match movement {
+            Move::LeftCastle { .. } if self.chess960 => "O-O-O".to_string(),
+            Move::RightCastle { .. } if self.chess960 => "O-O".to_string(),
+            _ => movement.to_uci(),
+        }
+    }
+
+    fn user_move(&mut self, text: &str) {
+        let Some(movement) = self.parse_move(text) else {
+            self.send(&format!("Illegal move: {text}"));
+            return;
+        };
+        self.play(movement);
+        if !self.report_result() && self.engine == Some(self.board.state().mover()) {
+            self.think();
+        }
+    }
+
+    fn undo(&mut self, plies: usize) {
+        for _ in 0..plies {
+            let Some(movement) = self.played.pop() else {
+                return;
+            };
+            self.board.unapply_mut(movement);
+        }
+    }
+
+    fn play(&mut self, movement: Move) {
+        self.board.apply_mut(movement);
+        self.played.push(movement);
+    }
+
+    fn think(&mut self) {
+        if self.report_result() {
+            return;
+        }
+
+        let (limits, time_manager) = self.time.limits(&self.board);
+        let post = self.post;
+        let out = &mut self.out;
+        let mut search =
+            Search::from_limits(&self.board, limits, Scorer::eval).with_table(&mut self.tt);
+        if post {
+            search = search.with_progress(|result, stats| {
+                let _ = writeln!(out, "{}", thinking(result, stats));
+                let _ = out.flush();
+            });
+        }
+        if let Some(time_manager) = time_manager {
+            search = search.with_time_manager(time_manager);
+        }
+
+        if let Some(movement) = search.find().movement {
+            self.send(&format!("move {}", self.move_text(movement)));
+            self.play(movement);
+            self.report_result();
+        }
+    }
+
+    /// Announces the result once the game is over, returning whether it is.
+    fn report_result(&mut self) -> bool {
+        let Some(outcome) = self.board.outcome() else {
+            return false;
+        };
+        let result = match outcome {
+            Outcome::Checkmate { winner: Color::W } => "1-0 {White mates}",
+            Outcome::Checkmate { winner: Color::B } => "0-1 {Black mates}",
+            Outcome::Stalemate => "1/2-1/2 {Stalemate}",
+            Outcome::InsufficientMaterial => "1/2-1/2 {Insufficient material}",
+            Outcome::FiftyMoveRule => "1/2-1/2 {Fifty move rule}",
+            Outcome::Repetition => "1/2-1/2 {Draw by repetition}",
+        };
+        self.send(result);
+        true
+    }
+
+    fn send(&mut self, line: &str) {
+        let _ = writeln!(self.out, "{line}");
+        let _ = self.out.flush();
+    }
+}
+
+/// Thinking output: `ply score time nodes pv`, with time in centiseconds.
+#[allow(clippy::cast_possible_wrap)]
+fn thinking(result: &SearchResult, stats: &Stats) -> String {
+    let score = match result.mate_dist {
+        Some(dist) if result.eval > 0 => MATE_REPORT + dist.div_ceil(2) as i32,
+        Some(dist) => -MATE_REPORT - dist.div_ceil(2) as i32,
+        None => result.eval,
+    };
+    let pv: Vec<_> = result.pv.iter().map(|m| m.to_uci()).collect();
+    format!(
+        "{} {score} {} {} {}",
+        result.depth,
+        stats.elapsed.as_millis() / 10,
+        stats.nodes + stats.qnodes,
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lines(sut: &Cecp<Vec<u8>>) -> Vec<&str> {
+        std::str::from_utf8(&sut.out).unwrap().lines().collect()
+    }
+
+    #[test]
+    fn protover() {
+        let mut sut = Cecp::new(Vec::new());
+        sut.handle("xboard");
+        sut.handle("protover 2");
+        sut.handle("ping 7");
+
+        let lines = lines(&sut);
+        assert!(lines[0].starts_with("feature myname=\"chessie\""));
+        assert!(lines[0].ends_with("done=1"));
+        assert_eq!("pong 7", lines[1]);
+    }
+
+    #[test]
+    fn force_and_undo() {
+        let mut sut = Cecp::new(Vec::new());
+        sut.handle("force");
+        sut.handle("usermove e2e4");
+        sut.handle("e7e5");
+        assert!(lines(&sut).is_empty());
+        assert_eq!(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            fen::encode(&sut.board)
+        );
+
+        sut.handle("undo");
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            fen::encode(&sut.board)
+        );
+        sut.handle("usermove e7e5");
+        sut.handle("remove");
+        assert_eq!(fen::encode(&Board::default()), fen::encode(&sut.board));
+    }
+
+    #[test]
+    fn illegal_and_unknown() {
+        let mut sut = Cecp::new(Vec::new());
+        sut.handle("usermove e2e5");
+        sut.handle("frobnicate");
+        assert_eq!(vec!["Illegal move: e2e5", "Error (unknown command): frobnicate"], lines(&sut));
+    }
+
+    #[test]
+    fn replies_to_user_move() {
+        let mut sut = Cecp::new(Vec::new());
+        sut.handle("new");
+        sut.handle("sd 1");
+        sut.handle("usermove e2e4");
+
+        let lines = lines(&sut);
+        assert_eq!(1, lines.len());
+        assert!(lines[0].starts_with("move "));
+        assert_eq!(Color::W, sut.board.state().mover());
+    }
+
+    #[test]
+    fn go_without_time_control() {
+        let mut sut = Cecp::new(Vec::new());
+        sut.handle("new");
+        let (limits, time_manager) = sut.time.limits(&sut.board);
+        assert_eq!(Some(DEFAULT_MOVETIME), limits.movetime);
+        assert!(time_manager.is_none());
+
+        sut.handle("go");
+        let lines = lines(&sut);
+        assert_eq!(1, lines.len());
+        assert!(lines[0].starts_with("move "));
+    }
+
+    #[test]
+    fn go_posts_and_mates() {
+        let mut sut = Cecp::new(Vec::new());
+        sut.handle("setboard 8/8/8/8/2Q4p/k6P/1N6/1K3B2 w - - 0 101");
+        sut.handle("post");
+        sut.handle("sd 2");
+        sut.handle("go");
+
+        let lines = lines(&sut);
+        assert!(lines[0].starts_with("1 100001 "));
+        assert!(lines[0].ends_with(" c4c3"));
+        assert_eq!(vec!["move c4c3", "1-0 {White mates}"], lines[lines.len() - 2..]);
+    }
+
+    #[test]
+    fn fischerandom_castling() {
+        let mut sut = Cecp::new(Vec::new());
+        sut.handle("variant fischerandom");
+        sut.handle("setboard 2rkr3/2p1p3/8/8/8/8/PP6/RK6 w A - 0 1");
+        sut.handle("sd 2");
+        sut.handle("go");
+        assert_eq!(vec!["move O-O-O", "1-0 {White mates}"], lines(&sut));
+
+        sut.handle("new");
+        sut.handle("force");
+        sut.handle("setboard 7k/8/8/8/8/8/PP6/RK6 w A - 0 1");
+        sut.handle("usermove O-O-O");
+        assert_eq!("7k/8/8/8/8/8/PP6/2KR4 b - - 1 1", fen::encode(&sut.board));
+    }
+
+    #[test]
+    fn level() {
+        let mut sut = Cecp::new(Vec::new());
+        sut.handle("level 40 0:30 2.5");
+        assert_eq!(40, sut.time.moves);
+        assert_eq!(Duration::from_secs(30), sut.time.base);
+        assert_eq!(Duration::from_millis(2500), sut.time.increment);
+
+        sut.handle("level 0 5 0");
+        assert_eq!(Duration::from_secs(300), sut.time.base);
+        assert!(sut.time.limits(&sut.board).1.is_some());
+
+        sut.handle("st 2");
+        let (limits, time_manager) = sut.time.limits(&sut.board);
+        assert_eq!(Some(Duration::from_secs(2)), limits.movetime);
+        assert!(time_manager.is_none());
+    }
+}
@@ -5,6 +5,9 @@ pub type BitBoard = u64;
 // CastlingRights of the player (left, right)
 pub(crate) type CastlingRights = (bool, bool);
 
+// Files the castling rooks of the player start on (left, right)
+pub(crate) type CastlingFiles = (u8, u8);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CastlingUpdate {
     Left,
@@ -0,0 +1,92 @@
+pub use decode::decode;
+pub use encode::encode;
+pub use error::EpdError;
+
+mod decode;
+mod encode;
+mod error;
+
+use crate::{board::Board, moves::Move, search::SearchResult};
+
+/// A position in Extended Position Description: the piece placement, side
+/// to move, castling rights and en passant square, annotated with
+/// operations.
+#[derive(Debug, Clone)]
+pub struct Epd {
+    pub board: Board,
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// `bm`: moves considered best.
+    BestMoves(Vec<Move>),
+    /// `am`: moves to avoid.
+    AvoidMoves(Vec<Move>),
+    /// `id`: name of the position within its suite.
+    Id(String),
+    /// `c0` to `c9`: free-form comments.
+    Comment(u8, String),
+    /// `ce`: evaluation in centipawns from the side to move.
+    CentipawnEval(i32),
+    /// `dm`: the side to move mates in this many moves.
+    DirectMate(u32),
+    /// Any other opcode with its operands, kept as written.
+    Other(String, Vec<String>),
+}
+
+impl Epd {
+    #[must_use]
+    pub fn id(&self) -> Option<&str> {
+        self.operations.iter().find_map(|op| match op {
+            Operation::Id(id) => Some(id.as_str()),
+            _ => None,
+        })
+    }
+
+    #[must_use]
+    pub fn best_moves(&self) -> &[Move] {
+        self.operations
+            .iter()
+            .find_map(|op| match op {
+                Operation::BestMoves(moves) => Some(moves.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn avoid_moves(&self) -> &[Move] {
+        self.operations
+            .iter()
+            .find_map(|op| match op {
+                Operation::AvoidMoves(moves) => Some(moves.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether `result` satisfies the `bm`, `am` and `dm` operations, or
+    /// `None` when the position has none of them.
+    #[must_use]
+    pub fn check(&self, result: &SearchResult) -> Option<bool> {
+        let mut checked = false;
+        let mut solved = true;
+        for operation in &self.operations {
+            let passed = match operation {
+                Operation::BestMoves(moves) => result.movement.is_some_and(|m| moves.contains(&m)),
+                Operation::AvoidMoves(moves) => {
+                    result.movement.is_some_and(|m| !moves.contains(&m))
+                }
+                Operation::DirectMate(moves) => {
+                    let plies = (*moves as usize * 2).saturating_sub(1);
+                    result.eval > 0 && result.mate_dist.is_some_and(|dist| dist <= plies)
+                }
+                _ => continue,
+            };
+            checked = true;
+            solved &= passed;
+        }
+        checked.then_some(solved)
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{board::Board, fen, moves::Move, san};
+
+use super::{Epd, EpdError, Operation};
+
+/// Parses a line of EPD. The halfmove clock and fullmove number are taken
+/// from the `hmvc` and `fmvn` operations when present.
+pub fn decode(input: &str) -> Result<Epd, EpdError> {
+    let input = input.trim();
+    let mut fields = Vec::with_capacity(4);
+    let mut rest = input;
+    for _ in 0..4 {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if end == 0 {
+            return Err(EpdError::Invalid);
+        }
+        fields.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    let raw = decode_operations(rest)?;
+    let operand = |opcode: &str| {
+        raw.iter().find(|(op, _)| op == opcode).and_then(|(_, operands)| operands.first())
+    };
+    let halfmove = operand("hmvc").map_or("0", String::as_str);
+    let fullmove = operand("fmvn").map_or("1", String::as_str);
+    let board = fen::decode(&format!("{} {halfmove} {fullmove}", fields.join(" ")))
+        .map_err(|_| EpdError::Invalid)?;
+
+    let operations = raw
+        .into_iter()
+        .map(|(opcode, operands)| decode_operation(&board, opcode, operands))
+        .collect::<Result<_, _>>()?;
+    Ok(Epd { board, operations })
+}
+
+/// Splits operations on semicolons, keeping quoted operands whole.
+fn decode_operations(input: &str) -> Result<Vec<(String, Vec<String>)>, EpdError> {
+    let mut operations = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            _ if ch.is_whitespace() => {}
+            ';' => {
+                let mut tokens = std::mem::take(&mut tokens).into_iter();
+                let opcode = tokens.next().ok_or(EpdError::Invalid)?;
+                operations.push((opcode, tokens.collect()));
+            }
+            '"' => {
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(ch) => token.push(ch),
+                        None => return Err(EpdError::Invalid),
+                    }
+                }
+                tokens.push(token);
+            }
+            _ => {
+                let mut token = ch.to_string();
+                while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace() && *ch != ';') {
+                    token.push(ch);
+                }
+                tokens.push(token);
+            }
+        }
+    }
+
+    if tokens.is_empty() { Ok(operations) } else { Err(EpdError::Invalid) }
+}
+
+fn decode_operation(
+    board: &Board,
+    opcode: String,
+    operands: Vec<String>,
+) -> Result<Operation, EpdError> {
+    let single = || match operands.as_slice() {
+        [operand] => Ok(operand.clone()),
+        _ => Err(EpdError::Invalid),
+    };
+
+    let operation = match opcode.as_str() {
+        "bm" => Operation::BestMoves(decode_moves(board, &operands)?),
+        "am" => Operation::AvoidMoves(decode_moves(board, &operands)?),
+        "id" => Operation::Id(single()?),
+        "ce" => Operation::CentipawnEval(single()?.parse().map_err(|_| EpdError::Invalid)?),
+        "dm" => Operation::DirectMate(single()?.parse().map_err(|_| EpdError::Invalid)?),
+        _ => match opcode.strip_prefix('c').and_then(|n| n.parse::<u8>().ok()) {
+            Some(n) if n <= 9 && opcode.len() == 2 => Operation::Comment(n, single()?),
+            _ => Operation::Other(opcode, operands),
+        },
+    };
+    Ok(operation)
+}
+
+fn decode_moves(board: &Board, operands: &[String]) -> Result<Vec<Move>, EpdError> {
+    if operands.is_empty() {
+        return Err(EpdError::Invalid);
+    }
+    operands
+        .iter()
+        .map(|text| san::decode(board, text).map_err(|_| EpdError::IllegalMove(text.clone())))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let epd = decode(
+            "1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - bm Qd1+; id \"BK.01\"; \
+             c0 \"Bratko-Kopec; first\"; ce 320; dm 2;",
+        )
+        .unwrap();
+
+        assert_eq!("1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - 0 1", fen::encode(&epd.board));
+        assert_eq!(Some("BK.01"), epd.id());
+        assert_eq!(vec!["d6d1"], epd.best_moves().iter().map(|m| m.to_uci()).collect::<Vec<_>>());
+        assert!(epd.avoid_moves().is_empty());
+        assert_eq!(
+            vec![
+                Operation::Comment(0, "Bratko-Kopec; first".to_string()),
+                Operation::CentipawnEval(320),
+                Operation::DirectMate(2),
+            ],
+            epd.operations[2..]
+        );
+    }
+
+    #[test]
+    fn test_decode_counters_and_other() {
+        let epd = decode(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 hmvc 0; fmvn 1; \
+             am e5 d5; pv e5 Nf3;",
+        )
+        .unwrap();
+
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            fen::encode(&epd.board)
+        );
+        assert_eq!(2, epd.avoid_moves().len());
+        assert_eq!(
+            Operation::Other("pv".to_string(), vec!["e5".to_string(), "Nf3".to_string()]),
+            epd.operations[3]
+        );
+    }
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - id")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - id \"open;")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - ce high;")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm;")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR X KQkq - id \"x\";")]
+    fn test_invalid(input: &str) {
+        assert_eq!(EpdError::Invalid, decode(input).expect_err("should be invalid EPD"));
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let input = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e5;";
+        assert_eq!(EpdError::IllegalMove("e5".to_string()), decode(input).unwrap_err());
+    }
+}
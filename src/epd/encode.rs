@@ -0,0 +1,63 @@
+use crate::{board::Board, fen, moves::Move, san};
+
+use super::{Epd, Operation};
+
+/// Writes `epd` as a single line, with moves in SAN.
+pub fn encode(epd: &Epd) -> String {
+    let position = fen::encode(&epd.board);
+    let mut out = position.split(' ').take(4).collect::<Vec<_>>().join(" ");
+
+    for operation in &epd.operations {
+        out.push(' ');
+        match operation {
+            Operation::BestMoves(moves) => encode_moves(&mut out, "bm", &epd.board, moves),
+            Operation::AvoidMoves(moves) => encode_moves(&mut out, "am", &epd.board, moves),
+            Operation::Id(id) => out.push_str(&format!("id {}", quote(id))),
+            Operation::Comment(n, comment) => out.push_str(&format!("c{n} {}", quote(comment))),
+            Operation::CentipawnEval(ce) => out.push_str(&format!("ce {ce}")),
+            Operation::DirectMate(dm) => out.push_str(&format!("dm {dm}")),
+            Operation::Other(opcode, operands) => {
+                out.push_str(opcode);
+                for operand in operands {
+                    out.push(' ');
+                    if operand.contains(|ch: char| ch.is_whitespace() || ch == ';') {
+                        out.push_str(&quote(operand));
+                    } else {
+                        out.push_str(operand);
+                    }
+                }
+            }
+        }
+        out.push(';');
+    }
+    out
+}
+
+fn encode_moves(out: &mut String, opcode: &str, board: &Board, moves: &[Move]) {
+    out.push_str(opcode);
+    for movement in moves {
+        out.push(' ');
+        out.push_str(&san::encode(board, *movement));
+    }
+}
+
+fn quote(input: &str) -> String {
+    format!("\"{input}\"")
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::epd::decode;
+
+    #[test_case("1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - bm Qd1+; id \"BK.01\";")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 am e5 d5; c3 \"a; b\";")]
+    #[test_case("8/8/8/8/8/8/8/k6K w - - ce -15; dm 3; pv \"two words\" Kg2;")]
+    #[test_case("8/8/8/8/8/8/8/k6K w - -")]
+    fn test_symmetry(input: &str) {
+        let epd = decode(input).expect("should be OK");
+        assert_eq!(input, encode(&epd));
+    }
+}
@@ -0,0 +1,16 @@
+use core::fmt;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum EpdError {
+    Invalid,
+    IllegalMove(String),
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpdError::Invalid => f.write_str("Invalid EPD"),
+            EpdError::IllegalMove(san) => write!(f, "Illegal move {san} in EPD"),
+        }
+    }
+}
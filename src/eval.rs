@@ -93,7 +93,7 @@ const CENTRALITY_BIAS: [i32; 64] = [
         if !matches!(piece, Piece::Knight | Piece::Bishop) {
             return 0;
         }
-        bits::pos(bb).into_iter().map(|sq| CENTRALITY_BIAS[sq as usize]).sum()
+        bits::pos(bb).map(|sq| CENTRALITY_BIAS[sq as usize]).sum()
     }
 }
 
@@ -1,4 +1,6 @@
-use crate::{board::Board, color::Color, defs::CastlingUpdate, piece::Piece};
+use crate::{
+    bits, board::Board, color::Color, defs::CastlingUpdate, magic::Masks, piece::Piece, pos,
+};
 
 use super::FenError;
 
@@ -26,6 +28,7 @@ pub fn decode(input: &str) -> Result<Board, FenError> {
     decode_fullmove(&mut board, fullmove)?;
 
     board.calculate_occupancies();
+    board.recompute_key();
 
     Ok(board)
 }
@@ -65,6 +68,9 @@ fn decode_mover(board: &mut Board, input: &str) -> Result<(), FenError> {
     Ok(())
 }
 
+/// Accepts standard `KQkq` letters as well as the rook files used by
+/// X-FEN and Shredder-FEN for Chess960, e.g. `HAha` or `Gb`. A `K` or `Q`
+/// refers to the outermost rook on that side of the king.
 fn decode_castling(board: &mut Board, input: &str) -> Result<(), FenError> {
     board.disable_castling(Color::W, CastlingUpdate::Both);
     board.disable_castling(Color::B, CastlingUpdate::Both);
@@ -74,29 +80,62 @@ fn decode_castling(board: &mut Board, input: &str) -> Result<(), FenError> {
     }
 
     for ch in input.chars() {
-        match ch {
-            'Q' => board.enable_castling(Color::W, CastlingUpdate::Left),
-            'K' => board.enable_castling(Color::W, CastlingUpdate::Right),
-            'q' => board.enable_castling(Color::B, CastlingUpdate::Right),
-            'k' => board.enable_castling(Color::B, CastlingUpdate::Left),
+        let color = if ch.is_ascii_uppercase() { Color::W } else { Color::B };
+        let row = color.piece_row() * 8;
+        let king =
+            bits::first_pos(board.get(color, Piece::King) & Masks::RANK_1 << row).map(pos::col);
+        let rooks = board.get(color, Piece::Rook);
+        let has_rook = |file: &u8| rooks & pos::bb(row + file) != 0;
+
+        let (update, file) = match ch.to_ascii_lowercase() {
+            'k' => {
+                let king = king.unwrap_or(4);
+                (CastlingUpdate::Right, (king + 1..8).rev().find(has_rook).unwrap_or(7))
+            }
+            'q' => (CastlingUpdate::Left, (0..king.unwrap_or(4)).find(has_rook).unwrap_or(0)),
+            file @ 'a'..='h' => {
+                let file = file as u8 - b'a';
+                match king.ok_or(FenError::Invalid)? {
+                    king if file < king => (CastlingUpdate::Left, file),
+                    king if file > king => (CastlingUpdate::Right, file),
+                    _ => return Err(FenError::Invalid),
+                }
+            }
             _ => return Err(FenError::Invalid),
         };
+
+        let (left, right) = board.state().castling_rooks(color);
+        let files = match update {
+            CastlingUpdate::Left => (file, pos::col(right)),
+            _ => (pos::col(left), file),
+        };
+        board.set_castling_files(color, files);
+        board.enable_castling(color, update);
     }
 
     Ok(())
 }
 
-fn decode_enpassant(_board: &mut Board, input: &str) -> Result<(), FenError> {
-    if input != "-" {
-        return Err(FenError::NotSupportedEnPassant);
+fn decode_enpassant(board: &mut Board, input: &str) -> Result<(), FenError> {
+    if input == "-" {
+        return Ok(());
+    }
+
+    let sq = pos::parse(input).ok_or(FenError::Invalid)?;
+    let expected_row = match board.state().mover() {
+        Color::B => 2,
+        Color::W => 5,
+    };
+    if pos::row(sq) != expected_row {
+        return Err(FenError::Invalid);
     }
+    board.set_en_passant(Some(sq));
     Ok(())
 }
 
-fn decode_halfmove(_board: &mut Board, input: &str) -> Result<(), FenError> {
-    if input != "0" {
-        return Err(FenError::NotSupportedHalfMove);
-    }
+fn decode_halfmove(board: &mut Board, input: &str) -> Result<(), FenError> {
+    let halfmove: usize = input.parse().map_err(|_| FenError::Invalid)?;
+    board.set_halfmove(halfmove);
     Ok(())
 }
 
@@ -127,13 +166,20 @@ mod test {
     use test_case::test_case;
 
     use super::*;
-    use crate::{fen::encode, util::print_board};
+    use crate::{defs::Sq, fen::encode, squares::*, util::print_board};
 
     #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")]
     #[test_case("rnbqkbnr/pppppppp/8/3P1p2/8/8/PPPPPPPP/RNBQKBNR b KQ - 0 7")]
     #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Qq - 0 25")]
     #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b Kk - 0 10")]
     #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 12")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 2 1")]
+    #[test_case("4k3/8/8/8/8/8/8/4K2R b K - 99 80")]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9")]
+    #[test_case("1r2k1r1/8/8/8/8/8/8/1R2K1RR w GQkq - 0 1")]
+    #[test_case("rk5r/8/8/8/8/8/8/RRK4R w Bk - 0 1")]
     fn test_symmetry(input: &str) {
         let decoded = &decode(input).expect("should be OK");
         print_board(decoded);
@@ -147,19 +193,30 @@ mod test {
     #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkXq - 0 1")]
     #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR/8/8/8/8 w KQkXq - 0 1")]
     #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR X KQkq - 0 1")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq a1 0 1")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e6 0 1")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq z6 0 1")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w E - 0 1")]
+    #[test_case("rnbq1bnr/ppppkppp/8/8/8/8/PPPPPPPP/RNBQKBNR b Ah - 0 1")]
     fn test_invalid_fen(input: &str) {
         assert_eq!(FenError::Invalid, decode(input).expect_err("should be invalid FEN"));
     }
 
-    #[test_case(
-        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq a1 0 1",
-        FenError::NotSupportedEnPassant
-    )]
-    #[test_case(
-        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 2 1",
-        FenError::NotSupportedHalfMove
-    )]
-    fn not_supported(input: &str, expected: FenError) {
-        assert_eq!(expected, decode(input).expect_err("should not be supported"));
+    #[test_case("r3k2r/8/8/8/8/8/8/4K3 b k - 0 1", (false, true))]
+    #[test_case("r3k2r/8/8/8/8/8/8/4K3 b q - 0 1", (true, false))]
+    fn test_black_castling(input: &str, expected: (bool, bool)) {
+        let board = decode(input).unwrap();
+        assert_eq!(expected, board.state().castling_rights(Color::B));
+    }
+
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", (F1, H1))]
+    #[test_case("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9", (F1, H1))]
+    #[test_case("1r2k1r1/8/8/8/8/8/8/1R2K1RR w GB - 0 1", (B1, G1))]
+    #[test_case("1r2k1r1/8/8/8/8/8/8/1R2K1RR w KQ - 0 1", (B1, H1))]
+    fn test_castling_rooks(input: &str, expected: (Sq, Sq)) {
+        let board = decode(input).unwrap();
+        assert_eq!((true, true), board.state().castling_rights(Color::W));
+        assert_eq!(expected, board.state().castling_rooks(Color::W));
     }
 }
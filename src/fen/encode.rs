@@ -1,7 +1,9 @@
 use crate::{
     board::{Board, GameState},
     color::Color,
+    defs::Sq,
     piece::Piece,
+    pos,
 };
 
 pub fn encode(board: &Board) -> String {
@@ -10,8 +12,8 @@ pub fn encode(board: &Board) -> String {
 
     encode_ranks(&mut out, board);
     encode_mover(&mut out, state);
-    encode_castling(&mut out, state);
-    encode_enpassant(&mut out);
+    encode_castling(&mut out, board);
+    encode_enpassant(&mut out, state);
     encode_moves(&mut out, state);
     out
 }
@@ -49,8 +51,11 @@ fn encode_mover(out: &mut String, state: &GameState) {
     });
 }
 
-fn encode_castling(out: &mut String, state: &GameState) {
+/// Writes `KQkq` letters, falling back to the rook file as in X-FEN when
+/// a Chess960 castling rook is not the outermost one on its side.
+fn encode_castling(out: &mut String, board: &Board) {
     out.push(' ');
+    let state = board.state();
 
     let (white_left, white_right) = state.castling_rights(Color::W);
     let (black_left, black_right) = state.castling_rights(Color::B);
@@ -60,28 +65,39 @@ fn encode_castling(out: &mut String, state: &GameState) {
         return;
     }
 
-    if white_right {
-        out.push('K');
-    }
-    if white_left {
-        out.push('Q');
-    }
-    if black_left {
-        out.push('k');
-    }
-    if black_right {
-        out.push('q');
+    for color in [Color::W, Color::B] {
+        let (left, right) = state.castling_rights(color);
+        let (left_rook, right_rook) = state.castling_rooks(color);
+        let rooks = board.get(color, Piece::Rook);
+        let row = color.piece_row() * 8;
+        let outer = |from: Sq, to: Sq| (from..to).all(|sq| rooks & pos::bb(sq) == 0);
+
+        if right {
+            let ch = if outer(right_rook + 1, row + 8) { 'k' } else { file_char(right_rook) };
+            out.push(piece_case(color, ch));
+        }
+        if left {
+            let ch = if outer(row, left_rook) { 'q' } else { file_char(left_rook) };
+            out.push(piece_case(color, ch));
+        }
     }
 }
 
-fn encode_enpassant(out: &mut String) {
+fn file_char(sq: Sq) -> char {
+    char::from(b'a' + pos::col(sq))
+}
+
+fn encode_enpassant(out: &mut String, state: &GameState) {
     out.push(' ');
-    out.push('-');
+    match state.en_passant() {
+        Some(sq) => out.push_str(&pos::str(sq)),
+        None => out.push('-'),
+    }
 }
 
 fn encode_moves(out: &mut String, state: &GameState) {
     out.push(' ');
-    out.push('0');
+    out.push_str(&state.halfmove().to_string());
     out.push(' ');
     out.push_str(&state.fullmove().to_string());
 }
@@ -96,7 +112,11 @@ const fn piece_to_fen(color: Color, piece: Piece) -> char {
         Piece::King => 'k',
     };
 
-    if matches!(color, Color::W) { fen.to_ascii_uppercase() } else { fen }
+    piece_case(color, fen)
+}
+
+const fn piece_case(color: Color, ch: char) -> char {
+    if matches!(color, Color::W) { ch.to_ascii_uppercase() } else { ch }
 }
 
 #[cfg(test)]
@@ -2,16 +2,12 @@ use core::fmt;
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum FenError {
-    NotSupportedEnPassant,
-    NotSupportedHalfMove,
     Invalid,
 }
 
 impl FenError {
     fn description(&self) -> &str {
         match self {
-            FenError::NotSupportedEnPassant => "Chessie does not support en-passant yet",
-            FenError::NotSupportedHalfMove => "Chessie does not support fifty-move rule yet",
             FenError::Invalid => "Invalid FEN",
         }
     }
@@ -1,107 +1,159 @@
-use std::io;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
 
-use board::Board;
+use board::{Board, Outcome};
 use color::Color;
-use defs::Sq;
 use eval::{Scorer, legacy::LegacyScorer};
-use moves::Move;
-use search::Search;
+use pgn::{Game, GameResult};
+use search::{Clock, Search, SearchLimits, TimeManager, TranspositionTable};
 use util::{print_board, print_hboard};
 
 pub mod bits;
 mod board;
+pub mod cecp;
 mod color;
 pub mod defs;
+pub mod epd;
 pub mod eval;
 pub mod fen;
 pub mod magic;
 mod moves;
+pub mod perft;
+pub mod pgn;
 mod piece;
 pub mod pos;
+pub mod san;
 pub mod search;
 pub mod squares;
+pub mod uci;
 pub mod util;
 
-fn read_sq() -> Sq {
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).unwrap();
-    let col: u8 = line.trim().parse().unwrap();
-    line.clear();
-    io::stdin().read_line(&mut line).unwrap();
-    let row: u8 = line.trim().parse().unwrap();
-    row * 8 + col
-}
-
-pub fn play() {
+/// Plays against the engine from the terminal, reading the user's moves as
+/// UCI text, e.g. "e2e4". Returns the game once it is over or input ends.
+pub fn play() -> Game {
     let legacy_eval = LegacyScorer::eval;
     let mut board = Board::default();
+    let mut game = Game::new(board.clone());
+    game.set_tag("Event", "chessie interactive game");
+    game.set_tag("White", "user");
+    game.set_tag("Black", "chessie");
     print_board(&board);
-    loop {
-        let from = read_sq();
-        // print_board(&board, &[board.generate_moves(from).map(|p| )]);
 
-        let to = read_sq();
+    let mut line = String::new();
+    loop {
+        if let Some(result) = game_over(&board) {
+            game.result = result;
+            return game;
+        }
 
-        board.apply_mut(Move::Slide { from, to, castling_update: None });
+        line.clear();
+        if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            return game;
+        }
+        let Some(movement) = board.parse_uci_move(line.trim()) else {
+            println!("illegal move {}", line.trim());
+            continue;
+        };
+        board.apply_mut(movement);
+        game.push(movement, None);
         print_board(&board);
 
+        if let Some(result) = game_over(&board) {
+            game.result = result;
+            return game;
+        }
+
         let result = Search::new(&board, 4, legacy_eval).find();
         if let Some(movement) = result.movement {
+            println!("{}", san::encode(&board, movement));
+            game.push(movement, Some(pgn::eval_comment(&result)));
             board.apply_mut(movement);
             print_board(&board);
-        } else {
-            println!("Game over");
-            return;
         }
     }
 }
 
-pub fn auto_play(moves: usize, depth: usize) {
+/// Result of the game once it is over, announcing how it ended.
+fn game_over(board: &Board) -> Option<GameResult> {
+    let outcome = board.outcome()?;
+    match outcome {
+        Outcome::Checkmate { winner } => println!("{winner:?} wins by checkmate"),
+        Outcome::Stalemate => println!("stalemate"),
+        Outcome::InsufficientMaterial => println!("draw by insufficient material"),
+        Outcome::FiftyMoveRule => println!("draw by fifty-move rule"),
+        Outcome::Repetition => println!("draw by threefold repetition"),
+    }
+    Some(outcome.into())
+}
+
+/// Plays the engine against itself, each side starting with `time` on its
+/// clock and gaining `increment` after every move. Returns the game, with
+/// each move commented with its search score when `comments` is set.
+pub fn auto_play(moves: usize, time: Duration, increment: Duration, comments: bool) -> Game {
     let white_eval = Scorer::eval;
     let black_eval = LegacyScorer::eval;
+    let mut white_tt = TranspositionTable::default();
+    let mut black_tt = TranspositionTable::default();
+    let mut clock = Clock::new(time, increment);
     let mut board = Board::default();
 
+    let mut game = Game::new(board.clone());
+    game.set_tag("Event", "chessie self-play");
+    game.set_tag("White", "chessie (Scorer)");
+    game.set_tag("Black", "chessie (LegacyScorer)");
+    game.set_tag("TimeControl", &format!("{}+{}", time.as_secs(), increment.as_secs()));
+
     for _ in 0..moves {
-        let pc = board.count_pieces();
-        let bonus = if pc < 6 {
-            3
-        } else if pc < 10 {
-            2
-        } else {
-            1
-        };
-        let depth = match board.state().mover() {
-            Color::B => depth,
-            Color::W => depth + bonus,
-        };
+        if let Some(result) = game_over(&board) {
+            game.result = result;
+            return game;
+        }
 
+        let mover = board.state().mover();
         let eval = match board.state().mover() {
             Color::B => black_eval,
             Color::W => white_eval,
         };
+        let tt = match board.state().mover() {
+            Color::B => &mut black_tt,
+            Color::W => &mut white_tt,
+        };
 
-        let result = Search::new(&board, depth, eval).find();
+        let start = Instant::now();
+        let result = Search::from_limits(&board, SearchLimits::default(), eval)
+            .with_table(tt)
+            .with_time_manager(TimeManager::new(&clock, mover))
+            .find();
+        if !clock.punch(mover, start.elapsed()) {
+            println!("{:?} loses on time", mover);
+            game.set_tag("Termination", "time forfeit");
+            game.result = match mover {
+                Color::W => GameResult::BlackWins,
+                Color::B => GameResult::WhiteWins,
+            };
+            return game;
+        }
 
         if let Some(movement) = result.movement {
+            let san = san::encode(&board, movement);
             if let Some(dist) = result.mate_dist {
-                println!("{movement}, mate in {dist}");
+                println!("{san}, mate in {dist}");
             } else {
-                println!("{movement}");
+                println!("{san}");
             }
+            game.push(movement, comments.then(|| pgn::eval_comment(&result)));
             board.apply_mut(movement);
             print_hboard(&board, &[movement.from()]);
-        } else {
-            if board.in_check(board.state().mover()) {
-                println!("{:?} wins by checkmate", board.state().mover().flip());
-                return;
-            }
-            println!("stalemate");
-            return;
         }
     }
+    game.result = game_over(&board).unwrap_or(GameResult::Unknown);
+    game
 }
 
 pub fn main() {
-    auto_play(500, 4);
-    // play();
+    let game = auto_play(500, Duration::from_secs(60), Duration::from_secs(1), true);
+    // let game = play();
+    println!("{}", pgn::encode(&game));
 }
@@ -1,8 +1,99 @@
-use crate::{color::Color, defs::BitBoard, pos};
+use crate::{
+    defs::{BitBoard, Sq},
+    pos,
+};
 
 pub(crate) use magic_movements::MagicMovements;
 
 mod magic_movements;
+mod slider_attacks;
+
+/// Squares a rook on `sq` attacks given the `occupancy` of the board,
+/// blockers included.
+#[must_use]
+#[inline]
+pub(crate) fn rook_attacks(sq: Sq, occupancy: BitBoard) -> BitBoard {
+    let sq = sq as usize;
+    let key = (occupancy & slider_attacks::ROOK_MASKS[sq])
+        .wrapping_mul(slider_attacks::ROOK_MAGICS[sq])
+        >> slider_attacks::ROOK_SHIFTS[sq];
+    #[allow(clippy::cast_possible_truncation)]
+    slider_attacks::ROOK_ATTACKS[slider_attacks::ROOK_OFFSETS[sq] + key as usize]
+}
+
+/// Squares a bishop on `sq` attacks given the `occupancy` of the board,
+/// blockers included.
+#[must_use]
+#[inline]
+pub(crate) fn bishop_attacks(sq: Sq, occupancy: BitBoard) -> BitBoard {
+    let sq = sq as usize;
+    let key = (occupancy & slider_attacks::BISHOP_MASKS[sq])
+        .wrapping_mul(slider_attacks::BISHOP_MAGICS[sq])
+        >> slider_attacks::BISHOP_SHIFTS[sq];
+    #[allow(clippy::cast_possible_truncation)]
+    slider_attacks::BISHOP_ATTACKS[slider_attacks::BISHOP_OFFSETS[sq] + key as usize]
+}
+
+/// Squares strictly between `a` and `b` when they share a rank, file or
+/// diagonal, empty otherwise.
+#[must_use]
+pub(crate) fn between(a: Sq, b: Sq) -> BitBoard {
+    let (from_a, from_b) = if rook_attacks(a, 0) & pos::bb(b) != 0 {
+        (rook_attacks(a, pos::bb(b)), rook_attacks(b, pos::bb(a)))
+    } else if bishop_attacks(a, 0) & pos::bb(b) != 0 {
+        (bishop_attacks(a, pos::bb(b)), bishop_attacks(b, pos::bb(a)))
+    } else {
+        return 0;
+    };
+    from_a & from_b
+}
+
+/// The whole rank, file or diagonal through `a` and `b`, empty when they
+/// are not aligned.
+#[must_use]
+pub(crate) const fn line(a: Sq, b: Sq) -> BitBoard {
+    let lines = [
+        MagicMovements::ROW_SLIDER[pos::row(a) as usize],
+        MagicMovements::COL_SLIDER[pos::col(a) as usize],
+        MagicMovements::DIAG_SLIDER[a as usize],
+        MagicMovements::ANTIDIAG_SLIDER[a as usize],
+    ];
+    let mut i = 0;
+    while i < lines.len() {
+        if a != b && lines[i] & pos::bb(b) != 0 {
+            return lines[i];
+        }
+        i += 1;
+    }
+    0
+}
+
+/// Rook attacks computed with hyperbola quintessence, which the magic
+/// tables are checked against.
+#[cfg(test)]
+const fn rook_attacks_hyper_quint(sq: Sq, occupancy: BitBoard) -> BitBoard {
+    hyper_quint(sq, occupancy, MagicMovements::COL_SLIDER[pos::col(sq) as usize])
+        | hyper_quint(sq, occupancy, MagicMovements::ROW_SLIDER[pos::row(sq) as usize])
+}
+
+/// Bishop attacks computed with hyperbola quintessence, which the magic
+/// tables are checked against.
+#[cfg(test)]
+const fn bishop_attacks_hyper_quint(sq: Sq, occupancy: BitBoard) -> BitBoard {
+    hyper_quint(sq, occupancy, MagicMovements::DIAG_SLIDER[sq as usize])
+        | hyper_quint(sq, occupancy, MagicMovements::ANTIDIAG_SLIDER[sq as usize])
+}
+
+/// Hyperbola quintessence: slides from `sq` both ways along the line in
+/// `mask` up to and including the first blocker.
+#[cfg(test)]
+const fn hyper_quint(sq: Sq, occupancy: BitBoard, mask: BitBoard) -> BitBoard {
+    let o = occupancy & mask;
+    let r = pos::bb(sq);
+    let line = (o.wrapping_sub(r.wrapping_mul(2)))
+        ^ (o.reverse_bits().wrapping_sub(r.reverse_bits().wrapping_mul(2))).reverse_bits();
+    line & mask
+}
 
 pub struct Masks;
 
@@ -106,33 +197,56 @@ impl Masks {
     pub const RANK_7: BitBoard = Self::RANK_6 << 8;
     pub const RANK_8: BitBoard = Self::RANK_7 << 8;
 
-    /// CASTLING
-    const CASTLE_WHITE_RIGHT: BitBoard = Masks::F1 | Masks::G1 | Masks::H1;
-    const CASTLE_BLACK_RIGHT: BitBoard = Masks::F8 | Masks::G8 | Masks::H8;
+    /// SQUARE COLORS
+    pub const DARK_SQUARES: BitBoard = 0xaa55_aa55_aa55_aa55;
+    pub const LIGHT_SQUARES: BitBoard = !Self::DARK_SQUARES;
+}
 
-    const CASTLE_WHITE_LEFT: BitBoard = Masks::A1 | Masks::B1 | Masks::C1 | Masks::D1;
-    const CASTLE_BLACK_LEFT: BitBoard = Masks::A8 | Masks::B8 | Masks::C8 | Masks::D8;
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{squares::*, util::print_bitboard};
 
-    pub(crate) const fn castle_left(color: Color) -> BitBoard {
-        match color {
-            Color::B => Self::CASTLE_BLACK_LEFT,
-            Color::W => Self::CASTLE_WHITE_LEFT,
-        }
+    /// Random occupancies, plus the empty and the full board.
+    fn occupancies() -> impl Iterator<Item = BitBoard> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let random = (0..2_000).map(move |_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state & state.rotate_left(21)
+        });
+        [0, Masks::ALL].into_iter().chain(random)
     }
 
-    pub(crate) const fn castle_right(color: Color) -> BitBoard {
-        match color {
-            Color::B => Self::CASTLE_BLACK_RIGHT,
-            Color::W => Self::CASTLE_WHITE_RIGHT,
+    #[test]
+    fn magic_matches_hyper_quint() {
+        for sq in 0..64 {
+            for occupancy in occupancies() {
+                assert_eq!(rook_attacks_hyper_quint(sq, occupancy), rook_attacks(sq, occupancy));
+                assert_eq!(
+                    bishop_attacks_hyper_quint(sq, occupancy),
+                    bishop_attacks(sq, occupancy)
+                );
+            }
         }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use crate::util::print_bitboard;
+    #[test]
+    fn between() {
+        assert_eq!(Masks::B1 | Masks::C1 | Masks::D1, super::between(A1, E1));
+        assert_eq!(Masks::D4 | Masks::E5, super::between(F6, C3));
+        assert_eq!(0, super::between(A1, B3));
+        assert_eq!(0, super::between(A1, B2));
+    }
 
-    use super::*;
+    #[test]
+    fn line() {
+        assert_eq!(Masks::FILE_E, super::line(E2, E7));
+        assert_eq!(Masks::RANK_3, super::line(H3, B3));
+        assert_eq!(0x8040_2010_0804_0201, super::line(C3, F6));
+        assert_eq!(0, super::line(A1, B3));
+    }
 
     #[test]
     fn print_magic_masks() {
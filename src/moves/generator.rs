@@ -3,15 +3,13 @@ use crate::{
     board::{Board, GameState},
     defs::{BitBoard, CastlingRights, CastlingUpdate, Sq},
     eval::score_material::piece_value,
-    magic::{MagicMovements, Masks},
-    moves,
+    magic::{self, MagicMovements, Masks},
     piece::Piece,
     pos,
-    squares::*,
     util::print_board,
 };
 
-use super::Move;
+use super::{Legality, Move, MoveList};
 
 #[derive(Debug)]
 pub(crate) struct Generator<'board> {
@@ -22,18 +20,19 @@ pub(crate) struct Generator<'board> {
     from: Sq,
     castling_rights: CastlingRights,
     castling_update: Option<CastlingUpdate>,
-    moves: Vec<Move>,
-    only_legal: bool,
+    legality: &'board Legality,
+    /// Squares the piece may move to without exposing its king.
+    allowed: BitBoard,
 }
 
 impl<'board> Generator<'board> {
-    pub(crate) fn from_board(board: &'board Board, from: Sq, only_legal: bool) -> Self {
+    pub(crate) fn from_board(board: &'board Board, from: Sq, legality: &'board Legality) -> Self {
         let (color, piece, _) = board.at(from).unwrap_or_else(|| {
             print_board(board);
             unreachable!("cannot generate moves for empty position {from:?}")
         });
 
-        Self::new(board, from, color, piece, only_legal)
+        Self::new(board, from, color, piece, legality)
     }
 
     pub(crate) fn new(
@@ -41,20 +40,14 @@ impl<'board> Generator<'board> {
         from: Sq,
         color: Color,
         piece: Piece,
-        only_legal: bool,
+        legality: &'board Legality,
     ) -> Self {
         let state = board.state();
         let castling_rights = state.castling_rights(color);
-        let castling_update = if only_legal {
-            if matches!(piece, Piece::King) {
-                calc_castling_king(castling_rights)
-            } else if matches!(piece, Piece::Rook) {
-                calc_castling_rook(color, from, castling_rights)
-            } else {
-                None
-            }
-        } else {
-            None
+        let castling_update = match piece {
+            Piece::King => calc_castling_king(castling_rights),
+            Piece::Rook => calc_castling_rook(state, color, from, castling_rights),
+            _ => None,
         };
 
         Self {
@@ -65,97 +58,121 @@ impl<'board> Generator<'board> {
             piece,
             castling_rights,
             castling_update,
-            moves: vec![],
-            only_legal,
+            legality,
+            allowed: legality.allowed(from),
         }
     }
 
-    #[must_use]
-    pub(crate) fn generate(mut self) -> Vec<Move> {
+    /// Pushes the legal moves of the piece onto `moves`. Legality is decided
+    /// without playing the move, so the board is never cloned and generating
+    /// moves does not allocate.
+    pub(crate) fn generate(&self, moves: &mut MoveList) {
         match self.piece {
             Piece::Pawn => match self.color {
-                Color::B => self.emit_black_pawn(),
-                Color::W => self.emit_white_pawn(),
+                Color::B => self.emit_black_pawn(moves),
+                Color::W => self.emit_white_pawn(moves),
             },
-            Piece::Rook => self.emit(self.cross()),
-            Piece::Bishop => self.emit(self.diag()),
-            Piece::Queen => self.emit(self.cross() | self.diag()),
+            Piece::Rook => self.emit(moves, self.cross()),
+            Piece::Bishop => self.emit(moves, self.diag()),
+            Piece::Queen => self.emit(moves, self.cross() | self.diag()),
             Piece::Knight => {
-                self.emit(MagicMovements::KNIGHT_MOVES[self.from as usize]);
+                self.emit(moves, MagicMovements::KNIGHT_MOVES[self.from as usize]);
             }
             Piece::King => {
-                self.emit_castling();
-                self.emit(MagicMovements::KING_MOVES[self.from as usize]);
+                self.emit_castling(moves);
+                self.emit(moves, MagicMovements::KING_MOVES[self.from as usize]);
             }
         };
-        self.moves
     }
 
-    const fn cross(&self) -> BitBoard {
-        let col = self.hyper_quint(MagicMovements::COL_SLIDER[pos::col(self.from) as usize]);
-        let row = self.hyper_quint(MagicMovements::ROW_SLIDER[pos::row(self.from) as usize]);
-        col | row
+    fn cross(&self) -> BitBoard {
+        magic::rook_attacks(self.from, self.board.occupancy())
     }
 
-    const fn diag(&self) -> BitBoard {
-        let diag = self.hyper_quint(MagicMovements::DIAG_SLIDER[self.from as usize]);
-        let antidiag = self.hyper_quint(MagicMovements::ANTIDIAG_SLIDER[self.from as usize]);
-        diag | antidiag
+    fn diag(&self) -> BitBoard {
+        magic::bishop_attacks(self.from, self.board.occupancy())
     }
 
-    fn emit_black_pawn(&mut self) {
+    fn emit_black_pawn(&self, moves: &mut MoveList) {
         let pawns = self.board.get(Color::B, Piece::Pawn) & pos::bb(self.from);
         let white_side = self.board.occupancy_side(Color::W);
-        let side_attack = (bits::southeast(pawns) & Masks::NOT_FILE_A & white_side)
-            | (bits::southwest(pawns) & Masks::NOT_FILE_H & white_side);
+        let attack = (bits::southeast(pawns) & Masks::NOT_FILE_A)
+            | (bits::southwest(pawns) & Masks::NOT_FILE_H);
+        let side_attack = attack & white_side;
+        let en_passant = attack & self.en_passant_target() & Masks::RANK_3;
 
         let first_push = bits::south(pawns) & !self.board.occupancy();
         let second_push = bits::south(first_push & Masks::RANK_6) & !self.board.occupancy();
         let pushes = first_push | second_push;
 
         if pos::row(self.from) == self.color.flip().pawn_row() {
-            self.emit_pawn_promos(side_attack);
-            self.emit_pawn_promos(pushes);
+            self.emit_pawn_promos(moves, side_attack);
+            self.emit_pawn_promos(moves, pushes);
         } else {
-            self.emit_takes(side_attack);
-            self.emit_slides(pushes);
+            self.emit_takes(moves, side_attack);
+            self.emit_en_passant(moves, en_passant);
+            self.emit_slides(moves, pushes);
         }
     }
 
-    fn emit_white_pawn(&mut self) {
+    fn emit_white_pawn(&self, moves: &mut MoveList) {
         let pawns = self.board.get(Color::W, Piece::Pawn) & pos::bb(self.from);
         let black_side = self.board.occupancy_side(Color::B);
-        let side_attack = (bits::northeast(pawns) & Masks::NOT_FILE_A & black_side)
-            | (bits::northwest(pawns) & Masks::NOT_FILE_H & black_side);
+        let attack = (bits::northeast(pawns) & Masks::NOT_FILE_A)
+            | (bits::northwest(pawns) & Masks::NOT_FILE_H);
+        let side_attack = attack & black_side;
+        let en_passant = attack & self.en_passant_target() & Masks::RANK_6;
 
         let first_push = bits::north(pawns) & !self.board.occupancy();
         let second_push = bits::north(first_push & Masks::RANK_3) & !self.board.occupancy();
         let pushes = first_push | second_push;
 
         if pos::row(self.from) == self.color.flip().pawn_row() {
-            self.emit_pawn_promos(side_attack);
-            self.emit_pawn_promos(pushes);
+            self.emit_pawn_promos(moves, side_attack);
+            self.emit_pawn_promos(moves, pushes);
         } else {
-            self.emit_takes(side_attack);
-            self.emit_slides(pushes);
+            self.emit_takes(moves, side_attack);
+            self.emit_en_passant(moves, en_passant);
+            self.emit_slides(moves, pushes);
         }
     }
 
-    fn emit_pawn_promos(&mut self, bb: BitBoard) {
-        if let Some(to) = bits::first_pos(bb) {
+    fn emit_pawn_promos(&self, moves: &mut MoveList, bb: BitBoard) {
+        for to in bits::pos(bb) {
             let taken_piece = self.board.at(to).map(|(_, piece, _)| piece);
+            let target_castling_update = if matches!(taken_piece, Some(Piece::Rook)) {
+                self.calc_castling_opponent(to)
+            } else {
+                None
+            };
             for promo_piece in Piece::PROMO {
-                self.push_move(Move::PawnPromo { from: self.from, to, promo_piece, taken_piece });
+                self.push_move(
+                    moves,
+                    Move::PawnPromo {
+                        from: self.from,
+                        to,
+                        promo_piece,
+                        taken_piece,
+                        target_castling_update,
+                    },
+                );
             }
         }
     }
 
-    fn emit_castling(&mut self) {
+    fn emit_en_passant(&self, moves: &mut MoveList, bb: BitBoard) {
+        if let Some(to) = bits::first_pos(bb) {
+            self.push_move(moves, Move::EnPassant { from: self.from, to });
+        }
+    }
+
+    fn en_passant_target(&self) -> BitBoard {
+        self.state.en_passant().map_or(0, pos::bb)
+    }
+
+    fn emit_castling(&self, moves: &mut MoveList) {
         let (left, right) = self.castling_rights;
         if left || right {
-            let occ = self.board.occupancy();
-            let side = self.board.occupancy_side(self.color);
-
             let castling_update = if left && right {
                 CastlingUpdate::Both
             } else if left {
@@ -163,109 +180,146 @@ impl<'board> Generator<'board> {
             } else {
                 CastlingUpdate::Right
             };
+            let (left_rook, right_rook) = self.state.castling_rooks(self.color);
 
             if right {
-                let right_msk = Masks::castle_right(self.color);
-                let right_sq = match self.color {
-                    Color::B => Masks::H8,
-                    Color::W => Masks::H1,
+                let movement = Move::RightCastle {
+                    mover: self.color,
+                    king: self.from,
+                    rook: right_rook,
+                    castling_update,
                 };
-                if (right_msk & occ == right_sq) && (right_msk & side == right_sq) {
-                    self.push_move(Move::RightCastle { mover: self.color, castling_update });
+                if self.can_castle(movement) {
+                    self.push_move(moves, movement);
                 }
             }
 
             if left {
-                let left_msk = Masks::castle_left(self.color);
-                let left_sq = match self.color {
-                    Color::B => Masks::A8,
-                    Color::W => Masks::A1,
+                let movement = Move::LeftCastle {
+                    mover: self.color,
+                    king: self.from,
+                    rook: left_rook,
+                    castling_update,
                 };
-                if (left_msk & occ == left_sq) && (left_msk & side) == left_sq {
-                    self.push_move(Move::LeftCastle { mover: self.color, castling_update });
+                if self.can_castle(movement) {
+                    self.push_move(moves, movement);
                 }
             }
         }
     }
 
-    fn emit(&mut self, bb: BitBoard) {
-        self.emit_takes(bb & self.board.occupancy_side(self.color.flip()));
-        self.emit_slides(bb & !self.board.occupancy());
+    /// The castling rook must still be in place, and every square the king
+    /// and rook cross or land on must be empty but for the two of them.
+    fn can_castle(&self, movement: Move) -> bool {
+        let (Move::LeftCastle { king, rook, .. } | Move::RightCastle { king, rook, .. }) = movement
+        else {
+            return false;
+        };
+        let Some((king_to, rook_to)) = movement.castle_targets() else {
+            return false;
+        };
+        if self.board.get(self.color, Piece::Rook) & pos::bb(rook) == 0 {
+            return false;
+        }
+
+        let path = span(king, king_to) | span(rook, rook_to);
+        let others = self.board.occupancy() & !pos::bb(king) & !pos::bb(rook);
+        path & others == 0
+    }
+
+    fn emit(&self, moves: &mut MoveList, bb: BitBoard) {
+        self.emit_takes(moves, bb & self.board.occupancy_side(self.color.flip()));
+        self.emit_slides(moves, bb & !self.board.occupancy());
     }
 
-    fn emit_slides(&mut self, bb: BitBoard) {
+    fn emit_slides(&self, moves: &mut MoveList, bb: BitBoard) {
         for to in bits::pos(bb) {
-            self.push_move(Move::Slide {
-                from: self.from,
-                to,
-                castling_update: self.castling_update,
-            });
+            self.push_move(
+                moves,
+                Move::Slide { from: self.from, to, castling_update: self.castling_update },
+            );
         }
     }
 
-    fn emit_takes(&mut self, bb: BitBoard) {
+    fn emit_takes(&self, moves: &mut MoveList, bb: BitBoard) {
         for to in bits::pos(bb) {
-            let (_, moved_piece, _) = self.board.at(self.from).unwrap();
-            let (_, taken_piece, _) = self.board.at(to).unwrap();
-            let value = piece_value(taken_piece) - piece_value(moved_piece);
-            self.push_move(Move::Takes {
-                from: self.from,
-                to,
-                piece: taken_piece,
-                value,
-                castling_update: self.castling_update,
-                target_castling_update: if matches!(taken_piece, Piece::Rook) {
-                    self.calc_castling_opponent(to)
-                } else {
-                    None
-                },
+            let (_, taken_piece, _) = self.board.at(to).unwrap_or_else(|| {
+                unreachable!("captures only target opponent pieces, {to:?} is empty")
             });
+            let value = piece_value(taken_piece) - piece_value(self.piece);
+            self.push_move(
+                moves,
+                Move::Takes {
+                    from: self.from,
+                    to,
+                    piece: taken_piece,
+                    value,
+                    castling_update: self.castling_update,
+                    target_castling_update: if matches!(taken_piece, Piece::Rook) {
+                        self.calc_castling_opponent(to)
+                    } else {
+                        None
+                    },
+                },
+            );
         }
     }
 
-    fn push_move(&mut self, m: Move) {
-        if !self.only_legal || self.is_legal(m) {
-            self.moves.push(m);
+    fn push_move(&self, moves: &mut MoveList, m: Move) {
+        if self.is_legal(m) {
+            moves.push(m);
         }
     }
 
-    fn is_legal(&mut self, movement: Move) -> bool {
-        let mut next = self.board.clone();
-        next.apply_mut(movement);
-        if matches!(movement, Move::LeftCastle { .. } | Move::RightCastle { .. })
-            && moves::is_attacked(
-                &next.pseudo_movements(self.color.flip()),
-                if matches!(movement, Move::LeftCastle { .. }) {
-                    left_xray(self.color)
-                } else {
-                    right_xray(self.color)
-                },
-            )
-        {
-            false
-        } else {
-            !next.in_check(self.color)
+    fn is_legal(&self, movement: Move) -> bool {
+        let opponent = self.color.flip();
+        match movement {
+            Move::LeftCastle { rook, .. } | Move::RightCastle { rook, .. } => {
+                // The king may not castle out of or through check. The squares
+                // it crosses are checked with both the king and its rook
+                // lifted, so neither of them hides an attack.
+                let Some((king_to, _)) = movement.castle_targets() else {
+                    return false;
+                };
+                let occupancy = self.board.occupancy() & !pos::bb(self.from) & !pos::bb(rook);
+                let crossed = span(self.from, king_to) & !pos::bb(self.from);
+                !self.legality.in_check()
+                    && bits::pos(crossed)
+                        .all(|sq| self.board.attackers(sq, opponent, occupancy) == 0)
+            }
+            // Taking en passant removes two pawns from the capturing rank,
+            // which may uncover an attack no pin accounts for, so the lines
+            // from the king are looked at again once both pawns are gone.
+            // Capturing a pawn that gives check is allowed by the check mask.
+            Move::EnPassant { to, .. } => {
+                let Some(king) = self.legality.king() else {
+                    return true;
+                };
+                let captured = pos::row(self.from) * 8 + pos::col(to);
+                if self.allowed & (pos::bb(to) | pos::bb(captured)) == 0 {
+                    return false;
+                }
+                let occupancy = (self.board.occupancy() & !pos::bb(self.from) & !pos::bb(captured))
+                    | pos::bb(to);
+                let queens = self.board.get(opponent, Piece::Queen);
+                let rooks = self.board.get(opponent, Piece::Rook) | queens;
+                let bishops = self.board.get(opponent, Piece::Bishop) | queens;
+                magic::rook_attacks(king, occupancy) & rooks == 0
+                    && magic::bishop_attacks(king, occupancy) & bishops == 0
+            }
+            // Lifting the king stops it from hiding the square behind it
+            // from a slider it is moving away from.
+            _ if self.piece == Piece::King => {
+                let occupancy = self.board.occupancy() & !pos::bb(self.from);
+                self.board.attackers(movement.to(), opponent, occupancy) == 0
+            }
+            _ => self.allowed & pos::bb(movement.to()) != 0,
         }
     }
 
-    const fn hyper_quint(&self, mask: BitBoard) -> BitBoard {
-        let o = self.board.occupancy() & mask;
-        let r = pos::bb(self.from);
-        let line = (o.wrapping_sub(r.wrapping_mul(2)))
-            ^ (o.reverse_bits().wrapping_sub(r.reverse_bits().wrapping_mul(2))).reverse_bits();
-        line & mask
-    }
-
     const fn calc_castling_opponent(&self, pos: Sq) -> Option<CastlingUpdate> {
         let color = self.color.flip();
-        let (left, right) = self.state.castling_rights(color);
-        if pos == right_rook(color) && right {
-            Some(CastlingUpdate::Right)
-        } else if pos == left_rook(color) && left {
-            Some(CastlingUpdate::Left)
-        } else {
-            None
-        }
+        calc_castling_rook(self.state, color, pos, self.state.castling_rights(color))
     }
 }
 
@@ -282,57 +336,39 @@ const fn calc_castling_king((left, right): CastlingRights) -> Option<CastlingUpd
 }
 
 const fn calc_castling_rook(
+    state: &GameState,
     color: Color,
     pos: Sq,
     (left, right): CastlingRights,
 ) -> Option<CastlingUpdate> {
-    if pos == right_rook(color) && right {
+    let (left_rook, right_rook) = state.castling_rooks(color);
+    if pos == right_rook && right {
         Some(CastlingUpdate::Right)
-    } else if pos == left_rook(color) && left {
+    } else if pos == left_rook && left {
         Some(CastlingUpdate::Left)
     } else {
         None
     }
 }
 
-const fn left_rook(color: Color) -> Sq {
-    match color {
-        Color::B => A8,
-        Color::W => A1,
-    }
-}
-
-const fn right_rook(color: Color) -> Sq {
-    match color {
-        Color::B => H8,
-        Color::W => H1,
-    }
-}
-
-const fn left_xray(color: Color) -> Sq {
-    match color {
-        Color::B => D8,
-        Color::W => D1,
-    }
-}
-
-const fn right_xray(color: Color) -> Sq {
-    match color {
-        Color::B => F8,
-        Color::W => F1,
-    }
+/// Squares of a rank from `a` to `b`, both included.
+const fn span(a: Sq, b: Sq) -> BitBoard {
+    let (low, high) = if a < b { (a, b) } else { (b, a) };
+    (BitBoard::MAX >> (63 - high)) & (BitBoard::MAX << low)
 }
 
 #[cfg(test)]
 mod test {
 
     use super::*;
-    use crate::{defs::Sq, fen, util::print_hboard};
+    use crate::{defs::Sq, fen, squares::*, util::print_hboard};
     use test_case::test_case;
 
     fn gen_squares(board: &Board, sq: Sq) -> Vec<Sq> {
-        let m = Generator::from_board(board, sq, true).generate();
-        moves::attacked_positions(&m).collect()
+        let legality = Legality::new(board, board.at(sq).unwrap().0);
+        let mut m = MoveList::new();
+        Generator::from_board(board, sq, &legality).generate(&mut m);
+        m.iter().map(|m| m.to()).collect()
     }
 
     #[test_case(B2, vec![C3, B3, B4])]
@@ -363,6 +399,21 @@ mod test {
         assert_eq!(expected, actual);
     }
 
+    #[test_case("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", E5, vec![D6, E6])]
+    #[test_case("rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP2PPP/RNBQKBNR b KQkq d3 0 2", E4, vec![D3, E3])]
+    #[test_case("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", E5, vec![E6])]
+    #[test_case("8/8/8/KPp4r/8/8/8/7k w - c6 0 1", B5, vec![B6])]
+    #[test_case("6b1/8/8/3pP3/8/8/K7/7k w - d6 0 2", E5, vec![E6])]
+    #[test_case("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1", E4, vec![D3])]
+    fn en_passant_gen(input: &str, at: Sq, expected: Vec<Sq>) {
+        let board = fen::decode(input).unwrap();
+        print_board(&board);
+
+        let actual = gen_squares(&board, at);
+        print_hboard(&board, &actual);
+        assert_eq!(expected, actual);
+    }
+
     #[test_case("8/8/8/6q1/5K2/8/4P3/8 w - - 0 2", F4, vec![G5, F3, E4])]
     #[test_case("8/8/8/6q1/8/8/3KP3/8 w - - 0 3", D2, vec![D1, E1, C2, C3, D3])]
     #[test_case("8/8/8/6q1/8/8/4P3/K7 w - - 0 4", A1, vec![B1, A2, B2])]
@@ -431,4 +482,47 @@ mod test {
         let actual = gen_squares(&board, E8);
         assert_eq!(vec![G8, C8, D8, F8], actual);
     }
+
+    #[test]
+    fn en_passant_into_list() {
+        // Either capture leaves the other white pawn shielding the king.
+        let board = fen::decode("8/8/8/KPpP3r/8/8/8/7k w - c6 0 1").unwrap();
+        let movements = board.movements(Color::W);
+        let captures: Vec<_> =
+            movements.iter().filter(|m| matches!(m, Move::EnPassant { .. })).collect();
+        assert_eq!(
+            vec![&Move::EnPassant { from: B5, to: C6 }, &Move::EnPassant { from: D5, to: C6 }],
+            captures
+        );
+    }
+
+    #[test_case("4k3/8/8/8/8/3n4/8/R3K2R w KQ - 0 1", vec![D1, F1, D2, E2])]
+    #[test_case("4k3/8/8/8/8/8/6p1/R3K2R w KQ - 0 1", vec![C1, D1, D2, E2, F2])]
+    fn emit_castling_in_check(input: &str, expected: Vec<Sq>) {
+        let board = fen::decode(input).unwrap();
+        print_board(&board);
+
+        let mut actual = gen_squares(&board, E1);
+        actual.sort_unstable();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn promo_captures() {
+        let board = fen::decode("rnb1k3/1P6/8/8/8/8/8/4K3 w q - 0 1").unwrap();
+        let legality = Legality::new(&board, Color::W);
+        let mut actual = MoveList::new();
+        Generator::from_board(&board, B7, &legality).generate(&mut actual);
+        assert_eq!(8, actual.len());
+
+        let capture = actual
+            .into_iter()
+            .find(|m| matches!(m, Move::PawnPromo { to: A8, promo_piece: Piece::Queen, .. }))
+            .unwrap();
+        let mut board = board;
+        board.apply_mut(capture);
+        assert_eq!((false, false), board.state().castling_rights(Color::B));
+        board.unapply_mut(capture);
+        assert_eq!((true, false), board.state().castling_rights(Color::B));
+    }
 }
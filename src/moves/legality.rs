@@ -0,0 +1,103 @@
+use crate::{
+    Color, bits,
+    board::Board,
+    defs::{BitBoard, Sq},
+    magic::{self, Masks},
+    piece::Piece,
+    pos,
+};
+
+/// Restrictions the side to move is under, computed once per position so
+/// moves can be checked for legality without playing them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Legality {
+    king: Option<Sq>,
+    /// Opponent pieces giving check.
+    checkers: BitBoard,
+    /// Squares a piece other than the king may move to: anywhere when not
+    /// in check, the checker or a square blocking it in single check and
+    /// nowhere in double check.
+    check_mask: BitBoard,
+    /// Pieces that would expose their king by leaving the line to it.
+    pinned: BitBoard,
+}
+
+impl Legality {
+    pub(crate) fn new(board: &Board, color: Color) -> Self {
+        let Some(king) = bits::first_pos(board.get(color, Piece::King)) else {
+            return Self { king: None, checkers: 0, check_mask: Masks::ALL, pinned: 0 };
+        };
+        let opponent = color.flip();
+        let checkers = board.attackers(king, opponent, board.occupancy());
+        let check_mask = match bits::count(checkers) {
+            0 => Masks::ALL,
+            1 => checkers | bits::first_pos(checkers).map_or(0, |sq| magic::between(king, sq)),
+            _ => 0,
+        };
+
+        // Sliders that would attack the king if it weren't for our own pieces.
+        let theirs = board.occupancy_side(opponent);
+        let queens = board.get(opponent, Piece::Queen);
+        let snipers = (magic::rook_attacks(king, theirs)
+            & (board.get(opponent, Piece::Rook) | queens))
+            | (magic::bishop_attacks(king, theirs) & (board.get(opponent, Piece::Bishop) | queens));
+        let pinned = bits::pos(snipers)
+            .map(|sniper| magic::between(king, sniper) & board.occupancy())
+            .filter(|blockers| bits::count(*blockers) == 1)
+            .fold(0, |pinned, blocker| pinned | (blocker & board.occupancy_side(color)));
+
+        Self { king: Some(king), checkers, check_mask, pinned }
+    }
+
+    #[must_use]
+    pub(crate) const fn king(&self) -> Option<Sq> {
+        self.king
+    }
+
+    #[must_use]
+    pub(crate) const fn in_check(&self) -> bool {
+        self.checkers != 0
+    }
+
+    /// Squares the piece on `from`, other than the king, may move to.
+    #[must_use]
+    pub(crate) const fn allowed(&self, from: Sq) -> BitBoard {
+        match self.king {
+            Some(king) if self.pinned & pos::bb(from) != 0 => {
+                self.check_mask & magic::line(king, from)
+            }
+            _ => self.check_mask,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{fen, squares::*};
+
+    #[test_case("4k3/8/8/8/8/8/8/4K3 w - - 0 1", 0, Masks::ALL, 0; "quiet")]
+    #[test_case("4k3/8/8/8/8/8/8/r3K3 w - - 0 1", Masks::A1, Masks::A1 | Masks::B1 | Masks::C1 | Masks::D1, 0; "rook check")]
+    #[test_case("4k3/8/8/8/8/3n4/8/4K3 w - - 0 1", Masks::D3, Masks::D3, 0; "knight check")]
+    #[test_case("4k3/8/8/8/8/3n4/8/r3K3 w - - 0 1", Masks::A1 | Masks::D3, 0, 0; "double check")]
+    #[test_case("4r3/8/8/8/8/8/4N3/4K3 w - - 0 1", 0, Masks::ALL, Masks::E2; "pinned knight")]
+    #[test_case("4r3/8/8/8/4n3/8/4N3/4K3 w - - 0 1", 0, Masks::ALL, 0; "two blockers")]
+    #[test_case("7k/8/8/8/8/2b5/1P6/K7 w - - 0 1", 0, Masks::ALL, Masks::B2; "pinned pawn")]
+    fn new(input: &str, checkers: BitBoard, check_mask: BitBoard, pinned: BitBoard) {
+        let board = fen::decode(input).unwrap();
+        let sut = Legality::new(&board, Color::W);
+        assert_eq!(checkers, sut.checkers);
+        assert_eq!(check_mask, sut.check_mask);
+        assert_eq!(pinned, sut.pinned);
+    }
+
+    #[test]
+    fn pinned_moves_along_the_line() {
+        let board = fen::decode("7k/8/8/8/3q4/8/1B6/K7 w - - 0 1").unwrap();
+        let sut = Legality::new(&board, Color::W);
+        assert_eq!(magic::line(A1, D4), sut.allowed(B2));
+        assert_eq!(Masks::ALL, sut.allowed(A1));
+    }
+}
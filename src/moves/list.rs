@@ -0,0 +1,138 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+use super::Move;
+
+/// Most legal moves any position has is 218, so a list this long never
+/// overflows.
+pub(crate) const MAX_MOVES: usize = 256;
+
+/// Fixed-capacity list of moves kept on the stack, so generating moves never
+/// allocates. Derefs to the slice of moves pushed so far.
+#[derive(Clone)]
+pub(crate) struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    /// Placeholder for the unused tail of the list, never read.
+    const EMPTY: Move = Move::EnPassant { from: 0, to: 0 };
+
+    #[must_use]
+    pub(crate) const fn new() -> Self {
+        Self { moves: [Self::EMPTY; MAX_MOVES], len: 0 }
+    }
+
+    pub(crate) fn push(&mut self, movement: Move) {
+        debug_assert!(self.len < MAX_MOVES, "move list is full");
+        self.moves[self.len] = movement;
+        self.len += 1;
+    }
+
+    pub(crate) const fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter { list: self, index: 0 }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Owning iterator over a [`MoveList`].
+#[derive(Debug, Clone)]
+pub(crate) struct IntoIter {
+    list: MoveList,
+    index: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        let movement = self.list.get(self.index).copied()?;
+        self.index += 1;
+        Some(movement)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.list.len() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fen, squares::*};
+
+    #[test]
+    fn push_and_iterate() {
+        let mut list = MoveList::new();
+        assert!(list.is_empty());
+
+        let first = Move::Slide { from: E2, to: E4, castling_update: None };
+        let second = Move::Slide { from: G1, to: F3, castling_update: None };
+        list.push(first);
+        list.push(second);
+        assert_eq!(2, list.len());
+        assert_eq!(Some(&first), list.first());
+        assert!(list.contains(&second));
+
+        list.reverse();
+        assert_eq!(vec![second, first], list.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn holds_most_moves() {
+        let board = fen::decode("R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1").unwrap();
+        let movements = board.movements(board.state().mover());
+        assert_eq!(218, movements.len());
+        assert_eq!(218, movements.into_iter().len());
+    }
+}
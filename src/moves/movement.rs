@@ -6,7 +6,6 @@ use crate::{
     defs::{CastlingUpdate, Sq},
     piece::Piece,
     pos,
-    squares::*,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -29,13 +28,22 @@ pub enum Move {
         to: Sq,
         promo_piece: Piece,
         taken_piece: Option<Piece>,
+        target_castling_update: Option<CastlingUpdate>,
+    },
+    EnPassant {
+        from: Sq,
+        to: Sq,
     },
     LeftCastle {
         mover: Color,
+        king: Sq,
+        rook: Sq,
         castling_update: CastlingUpdate,
     },
     RightCastle {
         mover: Color,
+        king: Sq,
+        rook: Sq,
         castling_update: CastlingUpdate,
     },
 }
@@ -44,7 +52,10 @@ impl Move {
     #[must_use]
     pub(crate) const fn to(self) -> Sq {
         match self {
-            Move::Slide { to, .. } | Move::Takes { to, .. } | Move::PawnPromo { to, .. } => to,
+            Move::Slide { to, .. }
+            | Move::Takes { to, .. }
+            | Move::PawnPromo { to, .. }
+            | Move::EnPassant { to, .. } => to,
             Move::LeftCastle { mover, .. } => mover.piece_row() * 8 + 2,
             Move::RightCastle { mover, .. } => mover.piece_row() * 8 + 6,
         }
@@ -53,15 +64,71 @@ impl Move {
     #[must_use]
     pub(crate) const fn from(self) -> Sq {
         match self {
-            Move::Slide { from, .. } | Move::Takes { from, .. } | Move::PawnPromo { from, .. } => {
-                from
+            Move::Slide { from, .. }
+            | Move::Takes { from, .. }
+            | Move::PawnPromo { from, .. }
+            | Move::EnPassant { from, .. } => from,
+            Move::LeftCastle { king, .. } | Move::RightCastle { king, .. } => king,
+        }
+    }
+
+    /// Squares the king and rook end up on after castling, which are the
+    /// same as in standard chess wherever they started.
+    #[must_use]
+    pub(crate) const fn castle_targets(self) -> Option<(Sq, Sq)> {
+        match self {
+            Move::LeftCastle { mover, .. } => {
+                let row = mover.piece_row() * 8;
+                Some((row + 2, row + 3))
             }
-            Move::LeftCastle { mover, .. } | Move::RightCastle { mover, .. } => {
-                mover.piece_row() * 8 + 4
+            Move::RightCastle { mover, .. } => {
+                let row = mover.piece_row() * 8;
+                Some((row + 6, row + 5))
             }
+            _ => None,
         }
     }
 
+    /// Long algebraic notation as used by UCI, e.g. "e2e4", "e1g1" for a
+    /// castle or "e7e8q" for a promotion. Castling from a Chess960 setup
+    /// other than the standard one is written as the king taking its own
+    /// rook, as moving the king to its target square could be mistaken for
+    /// a regular king move.
+    #[must_use]
+    pub fn to_uci(self) -> String {
+        match self {
+            Move::LeftCastle { king, rook, .. } | Move::RightCastle { king, rook, .. }
+                if pos::col(king) != 4 || !matches!(pos::col(rook), 0 | 7) =>
+            {
+                self.to_uci_chess960()
+            }
+            _ => self.uci_text(self.to()),
+        }
+    }
+
+    /// Same as [`Move::to_uci`], except castling is always written as the
+    /// king taking its own rook, as expected with the `UCI_Chess960` option.
+    #[must_use]
+    pub fn to_uci_chess960(self) -> String {
+        match self {
+            Move::LeftCastle { rook, .. } | Move::RightCastle { rook, .. } => self.uci_text(rook),
+            _ => self.uci_text(self.to()),
+        }
+    }
+
+    fn uci_text(self, to: Sq) -> String {
+        let promo = match self {
+            Move::PawnPromo { promo_piece, .. } => match promo_piece {
+                Piece::Knight => "n",
+                Piece::Bishop => "b",
+                Piece::Rook => "r",
+                _ => "q",
+            },
+            _ => "",
+        };
+        format!("{}{}{promo}", pos::str(self.from()), pos::str(to))
+    }
+
     #[must_use]
     pub(crate) fn priority(self) -> i32 {
         match self {
@@ -69,6 +136,7 @@ impl Move {
             Move::LeftCastle { .. } => 10,
             Move::RightCastle { .. } => 10,
             Move::Takes { value, .. } => 100 + value,
+            Move::EnPassant { .. } => 100,
             Move::PawnPromo { .. } => 900,
         }
     }
@@ -77,8 +145,13 @@ impl Move {
         let mover = board.state().mover();
         let opponent = mover.flip();
 
+        board.push_history();
+        board.set_en_passant(None);
+        board.set_halfmove(board.state().halfmove() + 1);
+
         match self {
             Move::Takes { from, to, castling_update, target_castling_update, .. } => {
+                board.set_halfmove(0);
                 board.clear(to);
                 board.slide(from, to);
                 if let Some(update) = castling_update {
@@ -92,30 +165,38 @@ impl Move {
                 if let Some(update) = castling_update {
                     board.disable_castling(mover, update);
                 }
+                if matches!(board.at(from), Some((_, Piece::Pawn, _))) {
+                    board.set_halfmove(0);
+                    if from.abs_diff(to) == 16 {
+                        board.set_en_passant(Some(from.midpoint(to)));
+                    }
+                }
                 board.slide(from, to);
             }
-            Move::PawnPromo { from, to, promo_piece: piece, .. } => {
+            Move::PawnPromo { from, to, promo_piece: piece, target_castling_update, .. } => {
+                board.set_halfmove(0);
                 board.clear(from);
                 board.clear(to);
                 board.add(mover, piece, to);
+                if let Some(update) = target_castling_update {
+                    board.disable_castling(opponent, update);
+                }
             }
-            Move::LeftCastle { mover, castling_update } => {
-                board.disable_castling(mover, castling_update);
-                let (king_from, king_to, rook_from, rook_to) = match mover {
-                    Color::B => (E8, C8, A8, D8),
-                    Color::W => (E1, C1, A1, D1),
-                };
-                board.slide(king_from, king_to);
-                board.slide(rook_from, rook_to);
-            }
-            Move::RightCastle { mover, castling_update } => {
+            Move::EnPassant { from, to } => {
+                board.set_halfmove(0);
+                board.slide(from, to);
+                board.clear(en_passant_capture(from, to));
+            }
+            Move::LeftCastle { mover, king, rook, castling_update }
+            | Move::RightCastle { mover, king, rook, castling_update } => {
                 board.disable_castling(mover, castling_update);
-                let (king_from, king_to, rook_from, rook_to) = match mover {
-                    Color::B => (E8, G8, H8, F8),
-                    Color::W => (E1, G1, H1, F1),
-                };
-                board.slide(king_from, king_to);
-                board.slide(rook_from, rook_to);
+                let (king_to, rook_to) = self.castle_targets().unwrap_or((king, rook));
+                // Cleared first, as in Chess960 the king may land where its
+                // rook stood or the other way around.
+                board.clear(king);
+                board.clear(rook);
+                board.add(mover, Piece::King, king_to);
+                board.add(mover, Piece::Rook, rook_to);
             }
         }
     }
@@ -140,39 +221,46 @@ impl Move {
                     board.enable_castling(mover, update);
                 }
             }
-            Move::PawnPromo { from, to, taken_piece, .. } => {
+            Move::PawnPromo { from, to, taken_piece, target_castling_update, .. } => {
                 board.clear(to);
                 board.add(mover, Piece::Pawn, from);
                 if let Some(piece) = taken_piece {
                     board.add(opponent, piece, to);
                 }
+                if let Some(update) = target_castling_update {
+                    board.enable_castling(opponent, update);
+                }
             }
-            Move::LeftCastle { mover, castling_update } => {
-                board.enable_castling(mover, castling_update);
-                let (king_from, king_to, rook_from, rook_to) = match mover {
-                    Color::B => (C8, E8, D8, A8),
-                    Color::W => (C1, E1, D1, A1),
-                };
-                board.slide(king_from, king_to);
-                board.slide(rook_from, rook_to);
-            }
-            Move::RightCastle { mover, castling_update } => {
+            Move::EnPassant { from, to } => {
+                board.slide(to, from);
+                board.add(opponent, Piece::Pawn, en_passant_capture(from, to));
+            }
+            Move::LeftCastle { mover, king, rook, castling_update }
+            | Move::RightCastle { mover, king, rook, castling_update } => {
                 board.enable_castling(mover, castling_update);
-                let (king_from, king_to, rook_from, rook_to) = match mover {
-                    Color::B => (G8, E8, F8, H8),
-                    Color::W => (G1, E1, F1, H1),
-                };
-                board.slide(king_from, king_to);
-                board.slide(rook_from, rook_to);
+                let (king_to, rook_to) = self.castle_targets().unwrap_or((king, rook));
+                board.clear(king_to);
+                board.clear(rook_to);
+                board.add(mover, Piece::King, king);
+                board.add(mover, Piece::Rook, rook);
             }
         }
+
+        board.pop_history();
     }
 }
 
+/// The captured pawn sits beside the capturing pawn, on the rank it moved from.
+const fn en_passant_capture(from: Sq, to: Sq) -> Sq {
+    pos::row(from) * 8 + pos::col(to)
+}
+
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Move::Takes { from, to, .. } | Move::Slide { from, to, .. } => {
+            Move::Takes { from, to, .. }
+            | Move::Slide { from, to, .. }
+            | Move::EnPassant { from, to } => {
                 let from = pos::str(*from);
                 let to = pos::str(*to);
                 f.write_fmt(format_args!("{from} × {to}"))
@@ -191,6 +279,7 @@ mod test {
     use std::mem;
 
     use super::*;
+    use crate::{fen, squares::*};
 
     const FROM: Sq = B2;
     const TO: Sq = D4;
@@ -200,24 +289,54 @@ mod test {
         assert_eq!(TO, Move::Slide { from: FROM, to: TO, castling_update: None }.to());
         assert_eq!(
             TO,
-            Move::PawnPromo { from: FROM, to: TO, promo_piece: Piece::Pawn, taken_piece: None }
-                .to()
+            Move::PawnPromo {
+                from: FROM,
+                to: TO,
+                promo_piece: Piece::Pawn,
+                taken_piece: None,
+                target_castling_update: None
+            }
+            .to()
         );
         assert_eq!(
             C1,
-            Move::LeftCastle { mover: Color::W, castling_update: CastlingUpdate::Left }.to()
+            Move::LeftCastle {
+                mover: Color::W,
+                king: E1,
+                rook: A1,
+                castling_update: CastlingUpdate::Left
+            }
+            .to()
         );
         assert_eq!(
             C8,
-            Move::LeftCastle { mover: Color::B, castling_update: CastlingUpdate::Both }.to()
+            Move::LeftCastle {
+                mover: Color::B,
+                king: E8,
+                rook: A8,
+                castling_update: CastlingUpdate::Both
+            }
+            .to()
         );
         assert_eq!(
             G1,
-            Move::RightCastle { mover: Color::W, castling_update: CastlingUpdate::Right }.to()
+            Move::RightCastle {
+                mover: Color::W,
+                king: E1,
+                rook: H1,
+                castling_update: CastlingUpdate::Right
+            }
+            .to()
         );
         assert_eq!(
             G8,
-            Move::RightCastle { mover: Color::B, castling_update: CastlingUpdate::Both }.to()
+            Move::RightCastle {
+                mover: Color::B,
+                king: E8,
+                rook: H8,
+                castling_update: CastlingUpdate::Both
+            }
+            .to()
         );
     }
     #[test]
@@ -225,25 +344,115 @@ mod test {
         assert_eq!(FROM, Move::Slide { from: FROM, to: TO, castling_update: None }.from());
         assert_eq!(
             FROM,
-            Move::PawnPromo { from: FROM, to: TO, promo_piece: Piece::Pawn, taken_piece: None }
-                .from()
+            Move::PawnPromo {
+                from: FROM,
+                to: TO,
+                promo_piece: Piece::Pawn,
+                taken_piece: None,
+                target_castling_update: None
+            }
+            .from()
         );
         assert_eq!(
             E1,
-            Move::LeftCastle { mover: Color::W, castling_update: CastlingUpdate::Left }.from()
+            Move::LeftCastle {
+                mover: Color::W,
+                king: E1,
+                rook: A1,
+                castling_update: CastlingUpdate::Left
+            }
+            .from()
         );
         assert_eq!(
             E8,
-            Move::LeftCastle { mover: Color::B, castling_update: CastlingUpdate::Both }.from()
+            Move::LeftCastle {
+                mover: Color::B,
+                king: E8,
+                rook: A8,
+                castling_update: CastlingUpdate::Both
+            }
+            .from()
         );
         assert_eq!(
             E1,
-            Move::RightCastle { mover: Color::W, castling_update: CastlingUpdate::Right }.from()
+            Move::RightCastle {
+                mover: Color::W,
+                king: E1,
+                rook: H1,
+                castling_update: CastlingUpdate::Right
+            }
+            .from()
         );
         assert_eq!(
             E8,
-            Move::RightCastle { mover: Color::B, castling_update: CastlingUpdate::Both }.from()
+            Move::RightCastle {
+                mover: Color::B,
+                king: E8,
+                rook: H8,
+                castling_update: CastlingUpdate::Both
+            }
+            .from()
+        );
+    }
+
+    #[test]
+    fn double_push_sets_en_passant() {
+        let mut board = Board::default();
+        board.apply_mut(Move::Slide { from: E2, to: E4, castling_update: None });
+        assert_eq!(Some(E3), board.state().en_passant());
+
+        board.apply_mut(Move::Slide { from: G8, to: F6, castling_update: None });
+        assert_eq!(None, board.state().en_passant());
+
+        board.unapply_mut(Move::Slide { from: G8, to: F6, castling_update: None });
+        assert_eq!(Some(E3), board.state().en_passant());
+    }
+
+    #[test]
+    fn halfmove_clock() {
+        let mut board =
+            fen::decode("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 5 2").unwrap();
+        let knight = Move::Slide { from: G8, to: F6, castling_update: None };
+        let takes = Move::Takes {
+            from: F3,
+            to: E5,
+            piece: Piece::Pawn,
+            value: -180,
+            castling_update: None,
+            target_castling_update: None,
+        };
+
+        board.apply_mut(knight);
+        assert_eq!(6, board.state().halfmove());
+
+        board.apply_mut(takes);
+        assert_eq!(0, board.state().halfmove());
+
+        board.unapply_mut(takes);
+        assert_eq!(6, board.state().halfmove());
+
+        board.unapply_mut(knight);
+        assert_eq!(5, board.state().halfmove());
+
+        board.apply_mut(Move::Slide { from: D7, to: D6, castling_update: None });
+        assert_eq!(0, board.state().halfmove());
+    }
+
+    #[test]
+    fn en_passant_apply_unapply() {
+        let mut board =
+            fen::decode("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2").unwrap();
+        let original = board.clone();
+        let movement = Move::EnPassant { from: E5, to: D6 };
+
+        board.apply_mut(movement);
+        assert_eq!(
+            "rnbqkbnr/ppp1pppp/3P4/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+            fen::encode(&board)
         );
+
+        board.unapply_mut(movement);
+        assert_eq!(original, board);
     }
 
     #[test]
@@ -251,4 +460,41 @@ mod test {
         assert_eq!(12, mem::size_of::<Move>());
         assert_eq!(8, mem::size_of::<&Move>());
     }
+
+    #[test]
+    fn to_uci() {
+        assert_eq!("b2d4", Move::Slide { from: FROM, to: TO, castling_update: None }.to_uci());
+        assert_eq!("e5d6", Move::EnPassant { from: E5, to: D6 }.to_uci());
+        assert_eq!(
+            "a7b8n",
+            Move::PawnPromo {
+                from: A7,
+                to: B8,
+                promo_piece: Piece::Knight,
+                taken_piece: Some(Piece::Rook),
+                target_castling_update: None
+            }
+            .to_uci()
+        );
+        assert_eq!(
+            "e1c1",
+            Move::LeftCastle {
+                mover: Color::W,
+                king: E1,
+                rook: A1,
+                castling_update: CastlingUpdate::Left
+            }
+            .to_uci()
+        );
+        assert_eq!(
+            "e8g8",
+            Move::RightCastle {
+                mover: Color::B,
+                king: E8,
+                rook: H8,
+                castling_update: CastlingUpdate::Both
+            }
+            .to_uci()
+        );
+    }
 }
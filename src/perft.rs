@@ -0,0 +1,112 @@
+use crate::board::Board;
+
+/// Counts the leaf nodes of the legal move tree `depth` plies deep.
+#[must_use]
+pub fn perft(board: &Board, depth: usize) -> u64 {
+    let mut board = board.clone();
+    count(&mut board, depth)
+}
+
+/// Breaks the perft count down per legal move at the root, in generation
+/// order. Moves are given in UCI notation so the output can be compared
+/// line by line against other engines. Empty at depth 0, where no move is
+/// played.
+#[must_use]
+pub fn divide(board: &Board, depth: usize) -> Vec<(String, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut board = board.clone();
+    let mover = board.state().mover();
+    board
+        .movements(mover)
+        .into_iter()
+        .map(|movement| {
+            board.apply_mut(movement);
+            let nodes = count(&mut board, depth - 1);
+            board.unapply_mut(movement);
+            (movement.to_uci(), nodes)
+        })
+        .collect()
+}
+
+fn count(board: &mut Board, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let movements = board.movements(board.state().mover());
+    if depth == 1 {
+        return movements.len() as u64;
+    }
+
+    movements
+        .into_iter()
+        .map(|movement| {
+            board.apply_mut(movement);
+            let nodes = count(board, depth - 1);
+            board.unapply_mut(movement);
+            nodes
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fen;
+    use test_case::test_case;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_4_MIRRORED: &str =
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    #[test_case(START, 1, 20)]
+    #[test_case(START, 2, 400)]
+    #[test_case(START, 3, 8_902)]
+    #[test_case(START, 4, 197_281)]
+    #[test_case(KIWIPETE, 1, 48)]
+    #[test_case(KIWIPETE, 2, 2_039)]
+    #[test_case(KIWIPETE, 3, 97_862)]
+    #[test_case(POSITION_3, 1, 14)]
+    #[test_case(POSITION_3, 2, 191)]
+    #[test_case(POSITION_3, 3, 2_812)]
+    #[test_case(POSITION_3, 4, 43_238)]
+    #[test_case(POSITION_4, 1, 6)]
+    #[test_case(POSITION_4, 2, 264)]
+    #[test_case(POSITION_4, 3, 9_467)]
+    #[test_case(POSITION_4_MIRRORED, 1, 6)]
+    #[test_case(POSITION_4_MIRRORED, 2, 264)]
+    #[test_case(POSITION_4_MIRRORED, 3, 9_467)]
+    #[test_case(POSITION_5, 1, 44)]
+    #[test_case(POSITION_5, 2, 1_486)]
+    #[test_case(POSITION_5, 3, 62_379)]
+    #[test_case(POSITION_6, 1, 46)]
+    #[test_case(POSITION_6, 2, 2_079)]
+    #[test_case(POSITION_6, 3, 89_890)]
+    fn reference(input: &str, depth: usize, expected: u64) {
+        let board = fen::decode(input).unwrap();
+        assert_eq!(expected, perft(&board, depth));
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board = fen::decode(KIWIPETE).unwrap();
+        let divided = divide(&board, 2);
+        assert_eq!(48, divided.len());
+        assert_eq!(2_039, divided.iter().map(|(_, nodes)| nodes).sum::<u64>());
+    }
+
+    #[test]
+    fn depth_zero() {
+        let board = fen::decode(START).unwrap();
+        assert_eq!(1, perft(&board, 0));
+        assert!(divide(&board, 0).is_empty());
+    }
+}
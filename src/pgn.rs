@@ -0,0 +1,158 @@
+pub use decode::{Games, decode};
+pub use encode::encode;
+pub use error::PgnError;
+
+mod decode;
+mod encode;
+mod error;
+
+use crate::{
+    board::{Board, Outcome},
+    color::Color,
+    moves::Move,
+    search::SearchResult,
+};
+
+/// Outcome recorded in the movetext termination or the `Result` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl GameResult {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        }
+    }
+
+    fn parse(input: &str) -> Option<Self> {
+        match input {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl From<Outcome> for GameResult {
+    fn from(outcome: Outcome) -> Self {
+        match outcome.winner() {
+            Some(Color::W) => GameResult::WhiteWins,
+            Some(Color::B) => GameResult::BlackWins,
+            None => GameResult::Draw,
+        }
+    }
+}
+
+/// A game as read from or written to PGN: its tag pairs, the position it
+/// starts from and the moves of its main line. Each move has an optional
+/// comment, its NAGs and the variations played instead of it, which start
+/// from the position before the move, i.e. `board(ply)`.
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub start: Board,
+    pub moves: Vec<Move>,
+    pub comments: Vec<Option<String>>,
+    pub nags: Vec<Vec<u8>>,
+    pub variations: Vec<Vec<Variation>>,
+    pub result: GameResult,
+}
+
+/// Line played instead of a move, annotated like the main line of a
+/// [`Game`] and possibly holding variations of its own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Variation {
+    pub moves: Vec<Move>,
+    pub comments: Vec<Option<String>>,
+    pub nags: Vec<Vec<u8>>,
+    pub variations: Vec<Vec<Variation>>,
+}
+
+impl Variation {
+    /// Appends a move played from the current final position of the line.
+    pub fn push(&mut self, movement: Move, comment: Option<String>) {
+        self.moves.push(movement);
+        self.comments.push(comment);
+        self.nags.push(Vec::new());
+        self.variations.push(Vec::new());
+    }
+}
+
+impl Game {
+    #[must_use]
+    pub fn new(start: Board) -> Self {
+        Self {
+            tags: Vec::new(),
+            start,
+            moves: Vec::new(),
+            comments: Vec::new(),
+            nags: Vec::new(),
+            variations: Vec::new(),
+            result: GameResult::Unknown,
+        }
+    }
+
+    /// Sets the value of tag `name`, replacing a previous value.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Appends a move played from the current final position.
+    pub fn push(&mut self, movement: Move, comment: Option<String>) {
+        self.moves.push(movement);
+        self.comments.push(comment);
+        self.nags.push(Vec::new());
+        self.variations.push(Vec::new());
+    }
+
+    /// Value of the first tag pair named `name`.
+    #[must_use]
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Position after the first `ply` moves have been played.
+    #[must_use]
+    pub fn board(&self, ply: usize) -> Board {
+        let mut board = self.start.clone();
+        self.moves.iter().take(ply).for_each(|movement| board.apply_mut(*movement));
+        board
+    }
+
+    /// Every position of the game, from the start to the final one.
+    pub fn positions(&self) -> impl Iterator<Item = Board> + '_ {
+        let mut board = self.start.clone();
+        let moves = self.moves.iter().map(move |movement| {
+            board.apply_mut(*movement);
+            board.clone()
+        });
+        std::iter::once(self.start.clone()).chain(moves)
+    }
+}
+
+/// Comment describing a search result, as engines annotate their moves:
+/// the score in pawns from the mover's point of view, or the moves to
+/// mate, followed by the search depth. E.g. "+0.35/6" or "-M2/4".
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn eval_comment(result: &SearchResult) -> String {
+    let sign = if result.eval < 0 { '-' } else { '+' };
+    match result.mate_dist {
+        Some(dist) => format!("{sign}M{}/{}", dist.div_ceil(2), result.depth),
+        None => format!("{sign}{:.2}/{}", f64::from(result.eval.abs()) / 100.0, result.depth),
+    }
+}
@@ -0,0 +1,539 @@
+use std::{iter::Peekable, str::CharIndices};
+
+use crate::{
+    board::Board,
+    fen,
+    san::{self, SanError},
+};
+
+use super::{Game, GameResult, PgnError, Variation};
+
+/// Reads the games in `input` one at a time. A game that fails to parse is
+/// yielded as an error and reading resumes with the next one.
+#[must_use]
+pub fn decode(input: &str) -> Games<'_> {
+    Games {
+        lexer: Lexer { input, chars: input.char_indices().peekable(), pending: None },
+        index: 0,
+    }
+}
+
+/// Iterator over the games of a PGN input, see [`decode`].
+pub struct Games<'a> {
+    lexer: Lexer<'a>,
+    index: usize,
+}
+
+impl Iterator for Games<'_> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut token = self.lexer.next();
+        token.as_ref()?;
+        self.index += 1;
+        let mut reader = Reader::new(self.index);
+
+        while let Some(next) = token {
+            if !matches!(next, Token::Tag(..) | Token::Comment(_)) {
+                reader.in_movetext = true;
+            }
+            match next {
+                Token::Tag(..) if reader.in_movetext => {
+                    self.lexer.push_back(next);
+                    break;
+                }
+                Token::Tag(name, value) => reader.tags.push((name, value)),
+                Token::Open => reader.open(),
+                Token::Close => reader.close(),
+                Token::Result(result) if reader.depth == 0 => {
+                    reader.result = Some(result);
+                    break;
+                }
+                Token::Move(text) => reader.play(text),
+                Token::Comment(text) => reader.comment(text),
+                Token::Nag(nag) => reader.nag(nag),
+                Token::Invalid => reader.fail(PgnError::Syntax { game: reader.game }),
+                Token::Result(_) => {}
+            }
+            token = self.lexer.next();
+        }
+
+        Some(reader.finish())
+    }
+}
+
+/// State of the game being read: the lines are replayed as they go so each
+/// move can be resolved against its position. The main line is at the
+/// bottom of the stack, the variation being read at the top.
+struct Reader {
+    game: usize,
+    tags: Vec<(String, String)>,
+    start: Option<Board>,
+    lines: Vec<Line>,
+    result: Option<GameResult>,
+    depth: usize,
+    in_movetext: bool,
+    error: Option<PgnError>,
+}
+
+/// Line being read, with the position it has reached.
+struct Line {
+    variation: Variation,
+    board: Board,
+    /// Position before the last move, where a variation on it starts.
+    previous: Option<Board>,
+    /// Plies of the game played before the first move of the line.
+    ply: usize,
+}
+
+impl Line {
+    fn new(board: Board, ply: usize) -> Self {
+        Self { variation: Variation::default(), board, previous: None, ply }
+    }
+}
+
+impl Reader {
+    const fn new(game: usize) -> Self {
+        Self {
+            game,
+            tags: Vec::new(),
+            start: None,
+            lines: Vec::new(),
+            result: None,
+            depth: 0,
+            in_movetext: false,
+            error: None,
+        }
+    }
+
+    fn fail(&mut self, error: PgnError) {
+        self.error.get_or_insert(error);
+    }
+
+    fn start(&self) -> Result<Board, PgnError> {
+        match self.tag("FEN") {
+            Some(value) => fen::decode(value).map_err(|_| PgnError::Fen { game: self.game }),
+            None => Ok(Board::default()),
+        }
+    }
+
+    fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    fn play(&mut self, text: &str) {
+        if self.error.is_some() {
+            return;
+        }
+        if self.lines.is_empty() {
+            match self.start() {
+                Ok(start) => {
+                    self.lines.push(Line::new(start.clone(), 0));
+                    self.start = Some(start);
+                }
+                Err(err) => return self.fail(err),
+            }
+        }
+
+        let game = self.game;
+        let Some(line) = self.lines.last_mut() else {
+            return;
+        };
+        let ply = line.ply + line.variation.moves.len() + 1;
+        let san = text.trim_end_matches(['!', '?']);
+        match san::decode(&line.board, san) {
+            Ok(movement) => {
+                line.previous = Some(line.board.clone());
+                line.board.apply_mut(movement);
+                line.variation.push(movement, None);
+                if let Some(nag) = suffix_nag(&text[san.len()..])
+                    && let Some(nags) = line.variation.nags.last_mut()
+                {
+                    nags.push(nag);
+                }
+            }
+            Err(SanError::Ambiguous) => {
+                self.fail(PgnError::Ambiguous { game, ply, san: text.to_string() });
+            }
+            Err(SanError::Illegal | SanError::Invalid) => {
+                self.fail(PgnError::Illegal { game, ply, san: text.to_string() });
+            }
+        }
+    }
+
+    /// Starts a variation on the last move of the current line, replayed
+    /// from the position before that move.
+    fn open(&mut self) {
+        self.depth += 1;
+        if self.error.is_some() {
+            return;
+        }
+        let branch = self.lines.last().and_then(|line| {
+            let board = line.previous.clone()?;
+            Some(Line::new(board, line.ply + line.variation.moves.len() - 1))
+        });
+        match branch {
+            Some(branch) => self.lines.push(branch),
+            None => self.fail(PgnError::Syntax { game: self.game }),
+        }
+    }
+
+    /// Ends the current variation, attaching it to the move it replaces.
+    fn close(&mut self) {
+        if self.depth == 0 {
+            return self.fail(PgnError::Syntax { game: self.game });
+        }
+        self.depth -= 1;
+        if self.error.is_some() || self.lines.len() < 2 {
+            return;
+        }
+        if let Some(line) = self.lines.pop()
+            && let Some(parent) = self.lines.last_mut()
+            && let Some(variations) = parent.variation.variations.last_mut()
+        {
+            variations.push(line.variation);
+        }
+    }
+
+    /// Attaches a comment to the last move of the current line, joining it
+    /// to any comment already there. Comments before the first move of a
+    /// line are dropped.
+    fn comment(&mut self, text: &str) {
+        let Some(comment) =
+            self.lines.last_mut().and_then(|line| line.variation.comments.last_mut())
+        else {
+            return;
+        };
+        match comment {
+            Some(comment) => {
+                comment.push(' ');
+                comment.push_str(text);
+            }
+            None => *comment = Some(text.to_string()),
+        }
+    }
+
+    fn nag(&mut self, nag: u8) {
+        if let Some(nags) = self.lines.last_mut().and_then(|line| line.variation.nags.last_mut()) {
+            nags.push(nag);
+        }
+    }
+
+    fn finish(mut self) -> Result<Game, PgnError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        if self.depth > 0 {
+            return Err(PgnError::Syntax { game: self.game });
+        }
+        let result = self
+            .result
+            .or_else(|| self.tag("Result").and_then(GameResult::parse))
+            .unwrap_or(GameResult::Unknown);
+        let start = match self.start.take() {
+            Some(start) => start,
+            None => self.start()?,
+        };
+        let main = self.lines.pop().map(|line| line.variation).unwrap_or_default();
+        Ok(Game {
+            tags: self.tags,
+            start,
+            moves: main.moves,
+            comments: main.comments,
+            nags: main.nags,
+            variations: main.variations,
+            result,
+        })
+    }
+}
+
+/// NAG of a move suffix annotation such as "!" or "?!".
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+enum Token<'a> {
+    Tag(String, String),
+    Open,
+    Close,
+    Result(GameResult),
+    Move(&'a str),
+    Comment(&'a str),
+    Nag(u8),
+    Invalid,
+}
+
+/// Splits PGN into tag pairs, variation brackets, moves, comments, NAGs
+/// and results, dropping move numbers and escaped lines.
+struct Lexer<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    pending: Option<Token<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn push_back(&mut self, token: Token<'a>) {
+        self.pending = Some(token);
+    }
+
+    fn skip_line(&mut self) {
+        for (_, ch) in self.chars.by_ref() {
+            if ch == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn tag(&mut self) -> Token<'a> {
+        let mut name = String::new();
+        while let Some((_, ch)) = self.chars.next_if(|(_, ch)| *ch != '"' && *ch != ']') {
+            if !ch.is_whitespace() {
+                name.push(ch);
+            }
+        }
+        if self.chars.next_if(|(_, ch)| *ch == '"').is_none() || name.is_empty() {
+            return Token::Invalid;
+        }
+
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, ch)) => value.push(ch),
+                    None => return Token::Invalid,
+                },
+                Some((_, '"')) => break,
+                Some((_, ch)) => value.push(ch),
+                None => return Token::Invalid,
+            }
+        }
+
+        while self.chars.next_if(|(_, ch)| ch.is_whitespace()).is_some() {}
+        if self.chars.next_if(|(_, ch)| *ch == ']').is_none() {
+            return Token::Invalid;
+        }
+        Token::Tag(name, value)
+    }
+
+    fn symbol(&mut self, start: usize) -> Option<Token<'a>> {
+        let mut end = start + self.input[start..].chars().next().map_or(0, char::len_utf8);
+        while let Some((at, ch)) = self.chars.peek().copied() {
+            if ch.is_whitespace() || "{}()[];$".contains(ch) {
+                break;
+            }
+            end = at + ch.len_utf8();
+            self.chars.next();
+        }
+
+        let symbol = &self.input[start..end];
+        if let Some(result) = GameResult::parse(symbol) {
+            return Some(Token::Result(result));
+        }
+        // Move numbers, possibly glued to the move: "12.", "12...", "1.e4".
+        let text = match symbol.trim_start_matches(|ch: char| ch.is_ascii_digit()) {
+            rest if rest.starts_with('.') => rest.trim_start_matches('.'),
+            _ => symbol,
+        };
+        (!text.is_empty()).then_some(Token::Move(text))
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.pending.take() {
+            return Some(token);
+        }
+
+        loop {
+            let (at, ch) = self.chars.next()?;
+            let line_start = at == 0 || self.input.as_bytes()[at - 1] == b'\n';
+            match ch {
+                _ if ch.is_whitespace() => {}
+                '%' if line_start => self.skip_line(),
+                ';' => self.skip_line(),
+                '{' => {
+                    let Some((close, _)) = self.chars.by_ref().find(|(_, ch)| *ch == '}') else {
+                        return Some(Token::Invalid);
+                    };
+                    let text = self.input[at + 1..close].trim();
+                    if !text.is_empty() {
+                        return Some(Token::Comment(text));
+                    }
+                }
+                '$' => {
+                    let mut end = at + 1;
+                    while let Some((digit, _)) = self.chars.next_if(|(_, ch)| ch.is_ascii_digit()) {
+                        end = digit + 1;
+                    }
+                    return Some(
+                        self.input[at + 1..end].parse().map_or(Token::Invalid, Token::Nag),
+                    );
+                }
+                '[' => return Some(self.tag()),
+                '(' => return Some(Token::Open),
+                ')' => return Some(Token::Close),
+                '}' | ']' => return Some(Token::Invalid),
+                _ => {
+                    if let Some(token) = self.symbol(at) {
+                        return Some(token);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{moves::Move, pgn};
+
+    const GAMES: &str = r#"[Event "Casual \"blitz\""]
+[Site "?"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 {develops} Nc6 $1 3. Bb5 a6 (3... Nf6 4. O-O (4. d3 Bc5) Nxe4)
+4. Ba4 ; the Morphy defence
+Nf6 5.O-O Be7 1-0
+
+[Event "Endgame"]
+[SetUp "1"]
+[FEN "4k3/P7/8/8/8/8/8/4K3 w - - 0 1"]
+
+1. a8=Q+ Kd7 2. Qb7+ *
+"#;
+
+    #[test]
+    fn test_decode() {
+        let games: Vec<_> = decode(GAMES).collect::<Result<_, _>>().unwrap();
+        assert_eq!(2, games.len());
+
+        let game = &games[0];
+        assert_eq!(Some("Casual \"blitz\""), game.tag("Event"));
+        assert_eq!(Some("Bob"), game.tag("Black"));
+        assert_eq!(GameResult::WhiteWins, game.result);
+        assert_eq!(
+            "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6 e1g1 f8e7",
+            game.moves.iter().map(|m| m.to_uci()).collect::<Vec<_>>().join(" ")
+        );
+        assert_eq!(Some("develops"), game.comments[2].as_deref());
+        assert_eq!(1, game.comments.iter().flatten().count());
+        assert_eq!(vec![1], game.nags[3]);
+        assert_eq!(1, game.nags.iter().flatten().count());
+        assert_eq!(
+            "r1bqk2r/1pppbppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 w kq - 4 6",
+            fen::encode(&game.board(game.moves.len()))
+        );
+
+        let game = &games[1];
+        assert_eq!(GameResult::Unknown, game.result);
+        assert_eq!("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", fen::encode(&game.start));
+        let positions: Vec<_> = game.positions().map(|board| fen::encode(&board)).collect();
+        assert_eq!(4, positions.len());
+        assert_eq!("8/1Q1k4/8/8/8/8/8/4K3 b - - 2 2", positions[3]);
+    }
+
+    #[test]
+    fn test_variations() {
+        let game = decode(GAMES).next().unwrap().unwrap();
+        let uci = |moves: &[Move]| moves.iter().map(|m| m.to_uci()).collect::<Vec<_>>().join(" ");
+
+        assert_eq!(1, game.variations.iter().flatten().count());
+        let [variation] = game.variations[5].as_slice() else {
+            panic!("expected a variation on 3... a6");
+        };
+        assert_eq!("g8f6 e1g1 f6e4", uci(&variation.moves));
+        let [nested] = variation.variations[1].as_slice() else {
+            panic!("expected a variation on 4. O-O");
+        };
+        assert_eq!("d2d3 f8c5", uci(&nested.moves));
+
+        let mut board = game.board(5);
+        variation.moves.iter().for_each(|m| board.apply_mut(*m));
+        assert_eq!(
+            "r1bqkb1r/pppp1ppp/2n5/1B2p3/4n3/5N2/PPPP1PPP/RNBQ1RK1 w kq - 0 5",
+            fen::encode(&board)
+        );
+    }
+
+    #[test]
+    fn test_suffix_annotations() {
+        let game =
+            decode("1. e4!? e5 2. Qh5?! Nc6!! 3. Bc4 Nf6?? 4. Qxf7# 1-0").next().unwrap().unwrap();
+        assert_eq!(vec![vec![5], vec![], vec![6], vec![3], vec![], vec![4], vec![]], game.nags);
+    }
+
+    #[test]
+    fn test_illegal_move_in_variation() {
+        let input = "1. e4 e5 (1... c5 2. Nf3 (2. Ke3) d6) 2. Nf3 *";
+        let error = decode(input).next().unwrap().err();
+        assert_eq!(Some(PgnError::Illegal { game: 1, ply: 3, san: "Ke3".to_string() }), error);
+
+        let error = decode("(1. e4) 1. d4 *").next().unwrap().err();
+        assert_eq!(Some(PgnError::Syntax { game: 1 }), error);
+    }
+
+    #[test]
+    fn test_comments_round_trip() {
+        let input = "[Event \"?\"]\n{opening} 1. e4 {+0.35/6} e5 (1... c5 {sicilian}) \
+                     2. Nf3 {book} {still book} Nc6 *";
+        let game = decode(input).next().unwrap().unwrap();
+        assert_eq!(
+            vec![Some("+0.35/6"), None, Some("book still book"), None],
+            game.comments.iter().map(Option::as_deref).collect::<Vec<_>>()
+        );
+
+        let encoded = pgn::encode(&game);
+        assert!(
+            encoded.ends_with(
+                "1. e4 {+0.35/6} e5 (1... c5 {sicilian}) 2. Nf3 {book still book} Nc6 *\n"
+            )
+        );
+        let decoded = decode(&encoded).next().unwrap().unwrap();
+        assert_eq!(game.moves, decoded.moves);
+        assert_eq!(game.comments, decoded.comments);
+        assert_eq!(game.variations, decoded.variations);
+    }
+
+    #[test]
+    fn test_errors_resume() {
+        let input = "1. e4 e5 2. Ke3 Nc6 1-0\n\n\
+                     [FEN \"4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1\"]\n1. Nd2 *\n\n\
+                     [FEN \"not a fen\"]\n1. e4 *\n\n\
+                     1. d4 (1. e4 *\n\n\
+                     [Event \"?\"]\n1. d4 d5 1/2-1/2";
+        let games: Vec<_> = decode(input).collect();
+        assert_eq!(5, games.len());
+        assert_eq!(
+            Some(PgnError::Illegal { game: 1, ply: 3, san: "Ke3".to_string() }),
+            games[0].as_ref().err().cloned()
+        );
+        assert_eq!(
+            Some(PgnError::Ambiguous { game: 2, ply: 1, san: "Nd2".to_string() }),
+            games[1].as_ref().err().cloned()
+        );
+        assert_eq!(Some(PgnError::Fen { game: 3 }), games[2].as_ref().err().cloned());
+        assert_eq!(Some(PgnError::Syntax { game: 4 }), games[3].as_ref().err().cloned());
+        assert_eq!(GameResult::Draw, games[4].as_ref().unwrap().result);
+        assert_eq!(2, games[4].as_ref().unwrap().moves.len());
+    }
+
+    #[test]
+    fn test_error_display() {
+        let error = PgnError::Illegal { game: 3, ply: 17, san: "Nxe5".to_string() };
+        assert_eq!("game 3, ply 17: illegal move Nxe5", error.to_string());
+    }
+}
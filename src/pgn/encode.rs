@@ -0,0 +1,225 @@
+use crate::{board::Board, color::Color, fen, moves::Move, san};
+
+use super::{Game, Variation};
+
+/// Tags every PGN game carries, in the order they must appear.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// Longest line of movetext, as recommended by the PGN export format.
+const LINE_WIDTH: usize = 79;
+
+/// Writes `game` in PGN export format: the Seven Tag Roster first, then any
+/// other tags, the starting position when it is not the standard one, and
+/// the movetext in SAN terminated by the result.
+#[must_use]
+pub fn encode(game: &Game) -> String {
+    let mut out = String::new();
+    encode_tags(&mut out, game);
+    out.push('\n');
+    encode_movetext(&mut out, game);
+    out
+}
+
+fn encode_tags(out: &mut String, game: &Game) {
+    for (name, default) in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Result" => game.result.as_str(),
+            _ => game.tag(name).unwrap_or(default),
+        };
+        encode_tag(out, name, value);
+    }
+
+    let start = fen::encode(&game.start);
+    if start != fen::encode(&Board::default()) {
+        encode_tag(out, "SetUp", "1");
+        encode_tag(out, "FEN", &start);
+    }
+
+    for (name, value) in &game.tags {
+        let reserved = ["SetUp", "FEN"].contains(&name.as_str())
+            || SEVEN_TAG_ROSTER.iter().any(|(tag, _)| tag == name);
+        if !reserved {
+            encode_tag(out, name, value);
+        }
+    }
+}
+
+fn encode_tag(out: &mut String, name: &str, value: &str) {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    out.push_str(&format!("[{name} \"{value}\"]\n"));
+}
+
+fn encode_movetext(out: &mut String, game: &Game) {
+    let mut tokens = Vec::new();
+    let main = Line {
+        moves: &game.moves,
+        comments: &game.comments,
+        nags: &game.nags,
+        variations: &game.variations,
+    };
+    encode_line(&mut tokens, &game.start, &main);
+    tokens.push(game.result.as_str().to_string());
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    out.push_str(&line);
+    out.push('\n');
+}
+
+/// Moves of the main line or of a variation with their annotations.
+struct Line<'a> {
+    moves: &'a [Move],
+    comments: &'a [Option<String>],
+    nags: &'a [Vec<u8>],
+    variations: &'a [Vec<Variation>],
+}
+
+impl<'a> From<&'a Variation> for Line<'a> {
+    fn from(variation: &'a Variation) -> Self {
+        Self {
+            moves: &variation.moves,
+            comments: &variation.comments,
+            nags: &variation.nags,
+            variations: &variation.variations,
+        }
+    }
+}
+
+/// Writes the moves of `line` played from `start`, each followed by its
+/// NAGs, comment and variations. Black's move is numbered when it opens
+/// the line or follows a variation.
+fn encode_line(tokens: &mut Vec<String>, start: &Board, line: &Line) {
+    let mut board = start.clone();
+    let mut numbered = true;
+    for (ply, movement) in line.moves.iter().enumerate() {
+        let fullmove = board.state().fullmove();
+        match board.state().mover() {
+            Color::W => tokens.push(format!("{fullmove}.")),
+            Color::B if numbered => tokens.push(format!("{fullmove}...")),
+            Color::B => {}
+        }
+        tokens.push(san::encode(&board, *movement));
+        for nag in line.nags.get(ply).into_iter().flatten() {
+            tokens.push(format!("${nag}"));
+        }
+        if let Some(Some(comment)) = line.comments.get(ply) {
+            tokens.push(format!("{{{comment}}}"));
+        }
+
+        let variations = line.variations.get(ply).map_or(&[][..], Vec::as_slice);
+        for variation in variations {
+            let first = tokens.len();
+            encode_line(tokens, &board, &variation.into());
+            let last = tokens.len() - 1;
+            if last >= first {
+                tokens[first].insert(0, '(');
+                tokens[last].push(')');
+            }
+        }
+        numbered = !variations.is_empty();
+        board.apply_mut(*movement);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pgn::{self, GameResult};
+
+    fn game(start: Board, moves: &[&str]) -> Game {
+        let mut game = Game::new(start);
+        let mut board = game.start.clone();
+        for text in moves {
+            let movement = board.parse_uci_move(text).unwrap();
+            board.apply_mut(movement);
+            game.push(movement, None);
+        }
+        game
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut game = game(Board::default(), &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        game.set_tag("White", "Fool");
+        game.set_tag("Annotator", "chessie");
+        game.comments[3] = Some("+M1/2".to_string());
+        game.result = GameResult::BlackWins;
+
+        assert_eq!(
+            "[Event \"?\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Fool\"]\n\
+             [Black \"?\"]\n\
+             [Result \"0-1\"]\n\
+             [Annotator \"chessie\"]\n\
+             \n\
+             1. f3 e5 2. g4 Qh4# {+M1/2} 0-1\n",
+            encode(&game)
+        );
+    }
+
+    #[test]
+    fn test_encode_setup() {
+        let start = fen::decode("4k3/P7/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let mut game = game(start, &["e8d7", "a7a8q"]);
+        game.set_tag("Event", "Say \"hi\"");
+
+        let encoded = encode(&game);
+        assert!(encoded.contains("[Event \"Say \\\"hi\\\"\"]\n"));
+        assert!(encoded.contains("[SetUp \"1\"]\n[FEN \"4k3/P7/8/8/8/8/8/4K3 b - - 0 1\"]\n"));
+        assert!(encoded.ends_with("\n1... Kd7 2. a8=Q *\n"));
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let moves = [
+            "e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5a4", "g8f6", "e1g1", "f8e7", "f1e1",
+            "b7b5", "a4b3", "d7d6", "c2c3", "e8g8", "h2h3", "c6a5", "b3c2", "c7c5", "d2d4", "d8c7",
+        ];
+        let mut game = game(Board::default(), &moves);
+        game.comments[0] = Some("+0.35/6".to_string());
+        game.result = GameResult::Draw;
+
+        let encoded = encode(&game);
+        assert!(encoded.lines().all(|line| line.len() <= LINE_WIDTH));
+
+        let decoded = pgn::decode(&encoded).next().unwrap().unwrap();
+        assert_eq!(game.moves, decoded.moves);
+        assert_eq!(GameResult::Draw, decoded.result);
+        assert_eq!(Some("?"), decoded.tag("Event"));
+    }
+
+    #[test]
+    fn test_encode_variations() {
+        let input = "1. e4 e5 2. Nf3 Nc6 $1 3. Bb5 a6 (3... Nf6 4. O-O (4. d3 Bc5) Nxe4) 4. Ba4 *";
+        let game = pgn::decode(input).next().unwrap().unwrap();
+
+        let encoded = encode(&game);
+        assert!(encoded.replace('\n', " ").ends_with(
+            "1. e4 e5 2. Nf3 Nc6 $1 3. Bb5 a6 (3... Nf6 4. O-O (4. d3 Bc5) 4... Nxe4) 4. Ba4 * "
+        ));
+        let decoded = pgn::decode(&encoded).next().unwrap().unwrap();
+        assert_eq!(game.nags, decoded.nags);
+        assert_eq!(game.variations, decoded.variations);
+    }
+}
@@ -0,0 +1,26 @@
+use core::fmt;
+
+/// Problems found while reading PGN. Games are numbered from 1 in the
+/// order they appear in the input, plies from 1 at the start of the game.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PgnError {
+    Syntax { game: usize },
+    Fen { game: usize },
+    Illegal { game: usize, ply: usize, san: String },
+    Ambiguous { game: usize, ply: usize, san: String },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Syntax { game } => write!(f, "game {game}: invalid PGN"),
+            PgnError::Fen { game } => write!(f, "game {game}: invalid FEN tag"),
+            PgnError::Illegal { game, ply, san } => {
+                write!(f, "game {game}, ply {ply}: illegal move {san}")
+            }
+            PgnError::Ambiguous { game, ply, san } => {
+                write!(f, "game {game}, ply {ply}: ambiguous move {san}")
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use crate::defs::{BitBoard, Sq};
 
 #[must_use]
-pub(crate) const fn row(sq: Sq) -> u8 {
+pub const fn row(sq: Sq) -> u8 {
     sq >> 3
 }
 
@@ -27,6 +27,23 @@ pub(crate) fn str(sq: Sq) -> String {
     format!("{}{}", display_col(col(sq)), display_row(row(sq)))
 }
 
+#[must_use]
+pub(crate) fn parse(input: &str) -> Option<Sq> {
+    let mut chars = input.chars();
+    let col = match chars.next()? {
+        ch @ 'a'..='h' => ch as u8 - b'a',
+        _ => return None,
+    };
+    let row = match chars.next()? {
+        ch @ '1'..='8' => ch as u8 - b'1',
+        _ => return None,
+    };
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(row * 8 + col)
+}
+
 const fn display_col(col: u8) -> char {
     match col {
         0 => 'a',
@@ -98,4 +115,15 @@ mod test {
     fn test_str() {
         assert_eq!("d3", str(D3))
     }
+
+    #[test_case("a1", Some(A1))]
+    #[test_case("d3", Some(D3))]
+    #[test_case("h8", Some(H8))]
+    #[test_case("i1", None)]
+    #[test_case("a9", None)]
+    #[test_case("a", None)]
+    #[test_case("a1a", None)]
+    fn test_parse(input: &str, expected: Option<Sq>) {
+        assert_eq!(expected, parse(input));
+    }
 }
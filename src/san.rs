@@ -0,0 +1,31 @@
+pub use decode::decode;
+pub use encode::encode;
+pub use error::SanError;
+
+mod decode;
+mod encode;
+mod error;
+
+use crate::piece::Piece;
+
+const fn piece_letter(piece: Piece) -> Option<char> {
+    match piece {
+        Piece::Pawn => None,
+        Piece::Knight => Some('N'),
+        Piece::Bishop => Some('B'),
+        Piece::Rook => Some('R'),
+        Piece::Queen => Some('Q'),
+        Piece::King => Some('K'),
+    }
+}
+
+const fn letter_piece(letter: char) -> Option<Piece> {
+    match letter {
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{board::Board, moves::Move, piece::Piece, pos};
+
+use super::{SanError, letter_piece};
+
+/// Resolves a move in Standard Algebraic Notation against the legal moves
+/// of the side to move. Check and annotation suffixes are ignored, and so
+/// are missing or redundant capture marks and disambiguation.
+pub fn decode(board: &Board, input: &str) -> Result<Move, SanError> {
+    let input = input.trim_end_matches(['+', '#', '!', '?']);
+    let movements = board.movements(board.state().mover());
+
+    match input {
+        "O-O" | "0-0" => {
+            return movements
+                .into_iter()
+                .find(|m| matches!(m, Move::RightCastle { .. }))
+                .ok_or(SanError::Illegal);
+        }
+        "O-O-O" | "0-0-0" => {
+            return movements
+                .into_iter()
+                .find(|m| matches!(m, Move::LeftCastle { .. }))
+                .ok_or(SanError::Illegal);
+        }
+        _ => {}
+    }
+
+    let san = decode_parts(input)?;
+    let mut candidates = movements.into_iter().filter(|m| san.matches(board, *m));
+    let movement = candidates.next().ok_or(SanError::Illegal)?;
+    if candidates.next().is_some() {
+        return Err(SanError::Ambiguous);
+    }
+    Ok(movement)
+}
+
+struct San {
+    piece: Piece,
+    from_col: Option<u8>,
+    from_row: Option<u8>,
+    to: u8,
+    promo: Option<Piece>,
+}
+
+impl San {
+    fn matches(&self, board: &Board, movement: Move) -> bool {
+        let promo = match movement {
+            Move::PawnPromo { promo_piece, .. } => Some(promo_piece),
+            Move::LeftCastle { .. } | Move::RightCastle { .. } => return false,
+            _ => None,
+        };
+        let from = movement.from();
+        movement.to() == self.to
+            && promo == self.promo
+            && matches!(board.at(from), Some((_, piece, _)) if piece == self.piece)
+            && self.from_col.is_none_or(|col| col == pos::col(from))
+            && self.from_row.is_none_or(|row| row == pos::row(from))
+    }
+}
+
+fn decode_parts(input: &str) -> Result<San, SanError> {
+    let (input, promo) = match input.char_indices().last() {
+        Some((at, ch)) if ch.is_ascii_uppercase() => {
+            let piece = letter_piece(ch).filter(|p| *p != Piece::King).ok_or(SanError::Invalid)?;
+            (input[..at].trim_end_matches('='), Some(piece))
+        }
+        _ => (input, None),
+    };
+
+    let (piece, input) = match input.chars().next() {
+        Some(ch) if ch.is_ascii_uppercase() => {
+            (letter_piece(ch).ok_or(SanError::Invalid)?, &input[1..])
+        }
+        _ => (Piece::Pawn, input),
+    };
+
+    let squares: String = input.chars().filter(|ch| !matches!(ch, 'x' | ':' | '-')).collect();
+    if squares.len() < 2 || !squares.is_ascii() {
+        return Err(SanError::Invalid);
+    }
+    let (hint, to) = squares.split_at(squares.len() - 2);
+    let to = pos::parse(to).ok_or(SanError::Invalid)?;
+
+    let (mut from_col, mut from_row) = (None, None);
+    for ch in hint.chars() {
+        match ch {
+            'a'..='h' if from_col.is_none() => from_col = Some(ch as u8 - b'a'),
+            '1'..='8' if from_row.is_none() => from_row = Some(ch as u8 - b'1'),
+            _ => return Err(SanError::Invalid),
+        }
+    }
+
+    Ok(San { piece, from_col, from_row, to, promo })
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{fen, san};
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test_case(START, "e4", "e2e4")]
+    #[test_case(START, "Nf3", "g1f3")]
+    #[test_case(START, "Ng1f3", "g1f3")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "exd5", "e4d5")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "ed5", "e4d5")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", "exd6", "e5d6")]
+    #[test_case("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "O-O", "e1g1")]
+    #[test_case("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "0-0-0", "e8c8")]
+    #[test_case("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1", "Ra8#", "a1a8")]
+    #[test_case("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "axb8=Q+", "a7b8q")]
+    #[test_case("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a8N", "a7a8n")]
+    #[test_case("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "Nbd2", "b1d2")]
+    #[test_case("4k3/8/R7/8/8/8/8/R3K3 w - - 0 1", "R1a3", "a1a3")]
+    #[test_case("1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1", "Qa4d4", "a4d4")]
+    fn test_decode(input: &str, san: &str, expected: &str) {
+        let board = fen::decode(input).unwrap();
+        assert_eq!(expected, decode(&board, san).unwrap().to_uci());
+    }
+
+    #[test_case(START, "e5", SanError::Illegal)]
+    #[test_case(START, "O-O", SanError::Illegal)]
+    #[test_case(START, "Ke2", SanError::Illegal)]
+    #[test_case(START, "Zf3", SanError::Invalid)]
+    #[test_case(START, "e9", SanError::Invalid)]
+    #[test_case(START, "", SanError::Invalid)]
+    #[test_case("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "Nd2", SanError::Ambiguous)]
+    #[test_case("1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1", "Qad4", SanError::Ambiguous)]
+    fn test_decode_error(input: &str, san: &str, expected: SanError) {
+        let board = fen::decode(input).unwrap();
+        assert_eq!(expected, decode(&board, san).expect_err("should not decode"));
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let board =
+            fen::decode("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        for movement in board.movements(board.state().mover()) {
+            let san = san::encode(&board, movement);
+            assert_eq!(movement, decode(&board, &san).unwrap(), "{san}");
+        }
+    }
+}
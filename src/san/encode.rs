@@ -0,0 +1,108 @@
+use crate::{board::Board, moves::Move, piece::Piece, pos};
+
+use super::piece_letter;
+
+/// Renders a legal `movement` of the side to move in Standard Algebraic
+/// Notation, e.g. "Nbd7", "exd6", "e8=Q+" or "O-O#".
+pub fn encode(board: &Board, movement: Move) -> String {
+    let mut out = match movement {
+        Move::LeftCastle { .. } => "O-O-O".to_string(),
+        Move::RightCastle { .. } => "O-O".to_string(),
+        _ => encode_movement(board, movement),
+    };
+    encode_check(&mut out, board, movement);
+    out
+}
+
+fn encode_movement(board: &Board, movement: Move) -> String {
+    let (from, to) = (movement.from(), movement.to());
+    let piece = board.at(from).map_or(Piece::Pawn, |(_, piece, _)| piece);
+    let captures = matches!(
+        movement,
+        Move::Takes { .. } | Move::EnPassant { .. } | Move::PawnPromo { taken_piece: Some(_), .. }
+    );
+
+    let mut out = String::new();
+    match piece_letter(piece) {
+        Some(letter) => {
+            out.push(letter);
+            encode_disambiguation(&mut out, board, movement, piece);
+        }
+        None if captures => out.push_str(&pos::str(from)[..1]),
+        None => {}
+    }
+    if captures {
+        out.push('x');
+    }
+    out.push_str(&pos::str(to));
+    if let Move::PawnPromo { promo_piece, .. } = movement
+        && let Some(letter) = piece_letter(promo_piece)
+    {
+        out.push('=');
+        out.push(letter);
+    }
+    out
+}
+
+/// Adds the file, rank or both of the origin square when another piece of
+/// the same kind can reach the same destination.
+fn encode_disambiguation(out: &mut String, board: &Board, movement: Move, piece: Piece) {
+    let (from, to) = (movement.from(), movement.to());
+    let rivals: Vec<_> = board
+        .movements(board.state().mover())
+        .into_iter()
+        .filter(|m| m.to() == to && m.from() != from)
+        .filter(|m| matches!(board.at(m.from()), Some((_, p, _)) if p == piece))
+        .map(Move::from)
+        .collect();
+    if rivals.is_empty() {
+        return;
+    }
+
+    let square = pos::str(from);
+    if rivals.iter().all(|sq| pos::col(*sq) != pos::col(from)) {
+        out.push_str(&square[..1]);
+    } else if rivals.iter().all(|sq| pos::row(*sq) != pos::row(from)) {
+        out.push_str(&square[1..]);
+    } else {
+        out.push_str(&square);
+    }
+}
+
+fn encode_check(out: &mut String, board: &Board, movement: Move) {
+    let mut next = board.clone();
+    next.apply_mut(movement);
+    let opponent = next.state().mover();
+    if next.in_check(opponent) {
+        out.push(if next.movements(opponent).is_empty() { '#' } else { '+' });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::fen;
+
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4", "e4")]
+    #[test_case("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "g1f3", "Nf3")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "e4d5", "exd5")]
+    #[test_case("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", "e5d6", "exd6")]
+    #[test_case("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", "O-O")]
+    #[test_case("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8", "O-O-O")]
+    #[test_case("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "a1a8", "Ra8+")]
+    #[test_case("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1", "a1a8", "Ra8#")]
+    #[test_case("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q", "axb8=Q+")]
+    #[test_case("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8n", "a8=N")]
+    #[test_case("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "b1d2", "Nbd2")]
+    #[test_case("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "a1d1", "Rad1")]
+    #[test_case("4k3/8/R7/8/8/8/8/R3K3 w - - 0 1", "a1a3", "R1a3")]
+    #[test_case("1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1", "a4d4", "Qa4d4")]
+    #[test_case("1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1", "h4d4", "Qhd4")]
+    fn test_encode(input: &str, movement: &str, expected: &str) {
+        let board = fen::decode(input).unwrap();
+        let movement = board.parse_uci_move(movement).unwrap();
+        assert_eq!(expected, encode(&board, movement));
+    }
+}
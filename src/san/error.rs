@@ -0,0 +1,24 @@
+use core::fmt;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum SanError {
+    Invalid,
+    Illegal,
+    Ambiguous,
+}
+
+impl SanError {
+    fn description(&self) -> &str {
+        match self {
+            SanError::Invalid => "Invalid SAN",
+            SanError::Illegal => "Illegal move",
+            SanError::Ambiguous => "Ambiguous move",
+        }
+    }
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
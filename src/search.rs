@@ -1,21 +1,76 @@
-use crate::{board::Board, eval::MATE_SCORE, moves::Move};
+use std::{
+    cmp::Reverse,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::Board,
+    eval::{MATE_SCORE, score_material::piece_value},
+    moves::{Move, MoveList},
+    piece::Piece,
+};
+
+pub use self::limits::SearchLimits;
+pub use self::time::{Clock, TimeManager};
+use self::transposition::Bound;
+pub use self::transposition::TranspositionTable;
+
+mod limits;
+mod time;
+mod transposition;
 
 type EvalFn = fn(board: &Board) -> i32;
 
+type ProgressFn<'a> = Box<dyn FnMut(&SearchResult, &Stats) + 'a>;
+
 /// Search infinity. Must exceed any possible |eval| (mate scores are
 /// MATE_SCORE plus/minus ply) so windows never clip real values, while
 /// staying small enough that `-INF` is representable in i32.
 const INF: i32 = MATE_SCORE + 10_000;
 
-pub struct Search {
+/// Deepest ply a search can reach, used to tell mate scores apart.
+const MAX_PLY: usize = 256;
+
+/// Deepest iteration when the limits do not set a depth, leaving room below
+/// `MAX_PLY` for the quiescence search.
+const MAX_DEPTH: usize = 64;
+
+/// Nodes visited between checks of the clock.
+const CLOCK_INTERVAL: usize = 1024;
+
+/// Any |eval| at or above this bound is a mate score.
+#[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+
+/// Slack added to the material a capture wins before delta pruning it, so
+/// positional gains are not pruned away.
+const DELTA_MARGIN: i32 = 200;
+
+pub struct Search<'tt> {
     board: Board,
-    depth: usize,
+    limits: SearchLimits,
+    start: Instant,
+    deadline: Option<Instant>,
+    time: Option<TimeManager>,
+    stopped: bool,
+    horizon: usize,
+    pv: Vec<Move>,
+    follow_pv: bool,
+    /// Principal variation below each ply of the iteration in progress,
+    /// reused across nodes so the search does not allocate.
+    lines: Vec<MoveList>,
     eval_fn: EvalFn,
     nodes: usize,
+    qnodes: usize,
+    quiescence_checks: bool,
+    tt: Option<&'tt mut TranspositionTable>,
+    progress: Option<ProgressFn<'tt>>,
 }
 
 pub struct Stats {
     pub nodes: usize,
+    pub qnodes: usize,
+    pub elapsed: Duration,
 }
 
 #[derive(Debug)]
@@ -23,68 +78,251 @@ pub struct SearchResult {
     pub eval: i32,
     pub movement: Option<Move>,
     pub mate_dist: Option<usize>,
+    /// Expected line of play, starting with `movement`.
+    pub pv: Vec<Move>,
+    /// Depth of the deepest completed iteration.
+    pub depth: usize,
+    /// Score of each completed iteration, starting at depth 1.
+    pub iterations: Vec<i32>,
 }
 
-impl Search {
+impl<'tt> Search<'tt> {
     pub fn new(board: &Board, depth: usize, eval_fn: EvalFn) -> Self {
-        Self { board: board.clone(), depth, eval_fn, nodes: 0 }
+        Self::from_limits(board, SearchLimits::depth(depth), eval_fn)
+    }
+
+    pub fn from_limits(board: &Board, limits: SearchLimits, eval_fn: EvalFn) -> Self {
+        let mut board = board.clone();
+        board.reserve_plies(MAX_PLY);
+        Self {
+            board,
+            limits,
+            start: Instant::now(),
+            deadline: None,
+            time: None,
+            stopped: false,
+            horizon: 0,
+            pv: Vec::new(),
+            follow_pv: false,
+            lines: vec![MoveList::new(); MAX_DEPTH + 1],
+            eval_fn,
+            nodes: 0,
+            qnodes: 0,
+            quiescence_checks: false,
+            tt: None,
+            progress: None,
+        }
+    }
+
+    /// Uses `tt` to look up and record searched positions. Passing the same
+    /// table to successive searches of a game reuses their results.
+    #[must_use]
+    pub fn with_table(mut self, tt: &'tt mut TranspositionTable) -> Self {
+        self.tt = Some(tt);
+        self
+    }
+
+    /// Budgets the search from the game clock. The hard budget bounds the
+    /// search like a deadline, the soft one decides whether to start another
+    /// iteration.
+    #[must_use]
+    pub fn with_time_manager(mut self, time: TimeManager) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Calls `progress` after every completed iteration.
+    #[must_use]
+    pub fn with_progress(mut self, progress: impl FnMut(&SearchResult, &Stats) + 'tt) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Also searches quiet moves that give check on the first ply of the
+    /// quiescence search, not just captures and promotions.
+    #[must_use]
+    pub fn with_quiescence_checks(mut self, enabled: bool) -> Self {
+        self.quiescence_checks = enabled;
+        self
     }
 
     #[must_use]
     pub fn find(mut self) -> SearchResult {
-        self.negamax(0, (-INF, INF))
+        self.iterate()
     }
 
     #[must_use]
     pub fn find_with_stats(mut self) -> (SearchResult, Stats) {
-        (self.negamax(0, (-INF, INF)), Stats { nodes: self.nodes })
+        let result = self.iterate();
+        (result, self.stats())
+    }
+
+    fn stats(&self) -> Stats {
+        Stats { nodes: self.nodes, qnodes: self.qnodes, elapsed: self.start.elapsed() }
+    }
+
+    /// Iterative deepening: searches depth 1 up to the configured depth, each
+    /// iteration trying the principal variation of the previous one first.
+    /// Stops early when a limit is hit, keeping the last completed iteration,
+    /// or the best root move so far when the first one did not complete.
+    fn iterate(&mut self) -> SearchResult {
+        let start = Instant::now();
+        self.start = start;
+        self.deadline = self.limits.deadline_from(start);
+        if let Some(time) = self.time.as_mut() {
+            let hard = start + time.hard();
+            self.deadline = Some(self.deadline.map_or(hard, |deadline| deadline.min(hard)));
+            time.set_forced(self.board.movements(self.board.state().mover()).len() == 1);
+        }
+        let max_depth = self.limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        let mut iterations = Vec::with_capacity(max_depth);
+        let mut result = SearchResult::new(Vec::new(), 0, 0);
+
+        for depth in 1..=max_depth {
+            self.horizon = depth;
+            self.follow_pv = true;
+            let eval = self.negamax(0, (-INF, INF));
+            if self.stopped {
+                if depth == 1 {
+                    result = self.interrupted(eval);
+                }
+                break;
+            }
+            let iteration = SearchResult::new(self.lines[0].to_vec(), eval, 0);
+
+            if let Some(time) = self.time.as_mut()
+                && depth > 1
+            {
+                time.update(iteration.movement != result.movement, result.eval - iteration.eval);
+            }
+
+            result = iteration;
+            result.depth = depth;
+            iterations.push(result.eval);
+            self.pv.clone_from(&result.pv);
+            let stats = self.stats();
+            if let Some(progress) = self.progress.as_mut() {
+                progress(&result, &stats);
+            }
+
+            if result.mate_dist.is_some_and(|dist| dist <= depth)
+                || self.is_past_deadline()
+                || self.time.is_some_and(|time| time.should_stop(start.elapsed()))
+            {
+                break;
+            }
+        }
+
+        result.iterations = iterations;
+        result
+    }
+
+    /// Result of a first iteration cut short by a limit: the best root move
+    /// searched to the end, or the first legal move when there is none.
+    fn interrupted(&self, eval: i32) -> SearchResult {
+        if self.lines[0].is_empty() {
+            let first = self.board.movements(self.board.state().mover()).first().copied();
+            return SearchResult::new(first.into_iter().collect(), 0, 0);
+        }
+        SearchResult::new(self.lines[0].to_vec(), eval, 0)
+    }
+
+    /// Checks the limits, remembering once they are hit.
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped {
+            let nodes = self.nodes + self.qnodes;
+            self.stopped = self.limits.nodes.is_some_and(|max| nodes >= max)
+                || self.limits.is_stopped()
+                || (nodes.is_multiple_of(CLOCK_INTERVAL) && self.is_past_deadline());
+        }
+        self.stopped
+    }
+
+    fn is_past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
     }
 
+    /// Scores the position at `ply`, leaving its principal variation in
+    /// `lines[ply]`.
     #[must_use]
-    fn negamax(&mut self, ply: usize, (mut alpha, beta): (i32, i32)) -> SearchResult {
+    #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+    fn negamax(&mut self, ply: usize, (mut alpha, beta): (i32, i32)) -> i32 {
         self.nodes += 1;
+        self.lines[ply].clear();
+
+        if self.should_stop() {
+            return 0;
+        }
 
-        if ply == self.depth {
-            let eval = (self.eval_fn)(&self.board);
-            return SearchResult {
-                movement: None,
-                eval,
-                mate_dist: (eval <= -MATE_SCORE).then_some(0),
+        if ply > 0 && (self.board.is_repetition(2) || self.board.is_insufficient_material()) {
+            return 0;
+        }
+
+        if ply == self.horizon {
+            return self.quiescence(ply, 0, (alpha, beta));
+        }
+
+        let key = self.board.key();
+        let remaining = self.horizon - ply;
+        let entry = self.tt.as_ref().and_then(|tt| tt.probe(key, ply));
+        if let Some(entry) = entry
+            && ply > 0
+            && usize::from(entry.depth) >= remaining
+        {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.eval >= beta,
+                Bound::Upper => entry.eval <= alpha,
             };
+            if cutoff {
+                if let Some(movement) = entry.movement {
+                    self.lines[ply].push(movement);
+                }
+                return entry.eval;
+            }
         }
 
         let mover = self.board.state().mover();
         let mut movements = self.board.movements(mover);
         if movements.is_empty() {
-            if self.board.in_check(mover) {
-                return SearchResult {
-                    movement: None,
-                    eval: -MATE_SCORE + ply as i32,
-                    mate_dist: Some(0),
-                };
-            } else {
-                return SearchResult { movement: None, eval: 0, mate_dist: None };
-            }
+            return if self.board.in_check(mover) { -MATE_SCORE + ply as i32 } else { 0 };
         }
 
-        movements.sort_by(|a, b| b.priority().cmp(&a.priority()));
+        if ply > 0 && self.board.state().is_fifty_move_draw() {
+            return 0;
+        }
 
-        let fallback_move = movements.first().copied();
+        movements.sort_by_key(|m| Reverse(m.priority()));
+        let pv_move = if self.follow_pv { self.pv.get(ply).copied() } else { None };
+        for first in [entry.and_then(|entry| entry.movement), pv_move].into_iter().flatten() {
+            if let Some(idx) = movements.iter().position(|m| *m == first) {
+                movements[..=idx].rotate_right(1);
+            }
+        }
+        if pv_move != movements.first().copied() {
+            self.follow_pv = false;
+        }
+
+        let original_alpha = alpha;
         let mut best_eval = i32::MIN;
-        let mut best_move = fallback_move;
-        let mut mate_dist = None;
 
         for movement in movements {
             self.board.apply_mut(movement);
-            let result = self.negamax(ply + 1, (-beta, -alpha));
+            let eval = -self.negamax(ply + 1, (-beta, -alpha));
             self.board.unapply_mut(movement);
+            self.follow_pv = false;
+            if self.stopped {
+                // At the root, the best of the moves searched to the end.
+                return best_eval.max(-INF);
+            }
 
-            let eval = -result.eval;
             if eval > best_eval {
                 best_eval = eval;
-                best_move = Some(movement);
-                mate_dist = result.mate_dist.map(|d| d + 1);
-                if mate_dist == Some(1) {
+                let (line, rest) = self.lines[ply..].split_at_mut(1);
+                line[0].clear();
+                line[0].push(movement);
+                rest[0].iter().for_each(|m| line[0].push(*m));
+                if eval == MATE_SCORE - ply as i32 - 1 {
                     break;
                 }
             }
@@ -95,14 +333,129 @@ impl Search {
             }
         }
 
-        SearchResult { movement: best_move, eval: best_eval, mate_dist }
+        if let Some(tt) = self.tt.as_mut() {
+            let bound = if best_eval <= original_alpha {
+                Bound::Upper
+            } else if best_eval >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            tt.store(key, ply, remaining, bound, best_eval, self.lines[ply].first().copied());
+        }
+
+        best_eval
+    }
+
+    /// Resolves captures and promotions past the horizon until the position
+    /// is quiet, so the static eval is not taken in the middle of an exchange.
+    /// A side in check cannot stand pat, so every evasion is searched.
+    #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+    fn quiescence(&mut self, ply: usize, qply: usize, (mut alpha, beta): (i32, i32)) -> i32 {
+        self.qnodes += 1;
+
+        if self.should_stop() {
+            return 0;
+        }
+
+        let mover = self.board.state().mover();
+        let mut movements = self.board.movements(mover);
+        if movements.is_empty() {
+            return if self.board.in_check(mover) { -MATE_SCORE + ply as i32 } else { 0 };
+        }
+
+        let in_check = self.board.in_check(mover);
+        let mate = MATE_SCORE - ply as i32;
+        let stand_pat =
+            if in_check { -mate } else { (self.eval_fn)(&self.board).clamp(-mate, mate) };
+        if !in_check {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            if stand_pat + piece_value(Piece::Queen) + DELTA_MARGIN < alpha {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+
+        let checks = self.quiescence_checks && qply == 0;
+        movements.sort_by_key(|m| Reverse(m.priority()));
+
+        let mut best_eval = stand_pat;
+        for movement in movements {
+            let tactical = material_gain(movement);
+            match tactical {
+                _ if in_check => {}
+                Some(gain) if stand_pat + gain + DELTA_MARGIN < alpha => continue,
+                None if !checks => continue,
+                _ => {}
+            }
+
+            self.board.apply_mut(movement);
+            if !in_check && tactical.is_none() && !self.board.in_check(mover.flip()) {
+                self.board.unapply_mut(movement);
+                continue;
+            }
+            let eval = -self.quiescence(ply + 1, qply + 1, (-beta, -alpha));
+            self.board.unapply_mut(movement);
+            if self.stopped {
+                return 0;
+            }
+
+            best_eval = best_eval.max(eval);
+            alpha = alpha.max(eval);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best_eval
+    }
+}
+
+/// Material won by a capture or promotion, `None` for quiet moves.
+const fn material_gain(movement: Move) -> Option<i32> {
+    match movement {
+        Move::Takes { piece, .. } => Some(piece_value(piece)),
+        Move::EnPassant { .. } => Some(piece_value(Piece::Pawn)),
+        Move::PawnPromo { promo_piece, taken_piece, .. } => {
+            let taken = match taken_piece {
+                Some(piece) => piece_value(piece),
+                None => 0,
+            };
+            Some(piece_value(promo_piece) - piece_value(Piece::Pawn) + taken)
+        }
+        Move::Slide { .. } | Move::LeftCastle { .. } | Move::RightCastle { .. } => None,
+    }
+}
+
+impl SearchResult {
+    /// Builds the result of a node at `ply` from its principal variation,
+    /// deriving the distance to mate from mate scores.
+    #[allow(clippy::cast_sign_loss)]
+    fn new(pv: Vec<Move>, eval: i32, ply: usize) -> Self {
+        let mate_dist = (eval.abs() >= MATE_BOUND)
+            .then(|| ((MATE_SCORE - eval.abs()) as usize).saturating_sub(ply));
+        Self {
+            eval,
+            movement: pv.first().copied(),
+            mate_dist,
+            pv,
+            depth: 0,
+            iterations: Vec::new(),
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::{
+        sync::{Arc, atomic::AtomicBool},
+        time::Duration,
+    };
+
     use super::*;
-    use crate::{defs::Sq, eval::Scorer, fen, squares::*, util::print_hboard};
+    use crate::{color::Color, defs::Sq, epd, eval::Scorer, fen, squares::*, util::print_hboard};
     use test_case::test_case;
 
     #[test_case("8/8/8/8/2Q4p/k6P/1N6/1K3B2 w - - 0 101", C4, C3)]
@@ -118,6 +471,220 @@ mod test {
         assert_eq!(Some(Move::Slide { from, to, castling_update: None }), result.movement);
     }
 
+    #[test]
+    fn tactics_suite() {
+        for line in include_str!("../suites/tactics.epd").lines() {
+            let epd = epd::decode(line).unwrap();
+            let result = Search::new(&epd.board, 4, Scorer::eval).find();
+            assert_eq!(Some(true), epd.check(&result), "{}", epd.id().unwrap_or(line));
+        }
+    }
+
+    #[test_case("8/8/8/4k3/8/8/8/R3K3 w - - 99 80", true)]
+    #[test_case("8/8/8/4k3/8/8/8/R3K3 w - - 10 80", false)]
+    fn fifty_move_rule(input: &str, is_draw: bool) {
+        let board = fen::decode(input).unwrap();
+
+        let result = Search::new(&board, 2, Scorer::eval).find();
+        assert_eq!(is_draw, result.eval == 0);
+    }
+
+    #[test]
+    fn repetition_is_draw() {
+        let mut board = fen::decode("6nk/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+        let escape = Move::Slide { from: G8, to: F6, castling_update: None };
+        for movement in [
+            escape,
+            Move::Slide { from: D1, to: D2, castling_update: None },
+            Move::Slide { from: F6, to: G8, castling_update: None },
+            Move::Slide { from: D2, to: D1, castling_update: None },
+        ] {
+            board.apply_mut(movement);
+        }
+
+        let result = Search::new(&board, 1, Scorer::eval).find();
+        assert_eq!(Some(escape), result.movement);
+        assert_eq!(0, result.eval);
+    }
+
+    #[test]
+    fn mate_in_two_with_table() {
+        let mut tt = TranspositionTable::new(1);
+        let mut board = fen::decode("8/8/8/2Q5/7p/1k5P/1N6/1K3B2 w - - 0 101").unwrap();
+
+        for expected in [3, 2, 1] {
+            let result = Search::new(&board, 4, Scorer::eval).with_table(&mut tt).find();
+            assert_eq!(Some(expected), result.mate_dist);
+            board.apply_mut(result.movement.unwrap());
+        }
+    }
+
+    #[test]
+    fn table_does_not_change_result() {
+        let board =
+            fen::decode("r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 4")
+                .unwrap();
+        let mut tt = TranspositionTable::new(1);
+
+        let (plain, plain_stats) = Search::new(&board, 3, Scorer::eval).find_with_stats();
+        let (cached, cached_stats) =
+            Search::new(&board, 3, Scorer::eval).with_table(&mut tt).find_with_stats();
+        assert_eq!(plain.eval, cached.eval);
+        assert!(cached_stats.nodes <= plain_stats.nodes);
+
+        let (_, again) = Search::new(&board, 3, Scorer::eval).with_table(&mut tt).find_with_stats();
+        assert!(again.nodes < cached_stats.nodes);
+    }
+
+    #[test]
+    fn quiescence_sees_recapture() {
+        // Qxd5 wins a pawn at depth 1 unless the recapture exd5 is seen.
+        let board = fen::decode("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+
+        let (result, stats) = Search::new(&board, 1, Scorer::eval).find_with_stats();
+        assert_ne!(D5, result.movement.unwrap().to());
+        assert!(stats.qnodes > 0);
+    }
+
+    #[test]
+    fn quiescence_checks() {
+        let board = fen::decode("3qk3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+
+        let (_, without) = Search::new(&board, 1, Scorer::eval).find_with_stats();
+        let (_, with) =
+            Search::new(&board, 1, Scorer::eval).with_quiescence_checks(true).find_with_stats();
+        assert!(with.qnodes > without.qnodes);
+    }
+
+    #[test]
+    fn quiescence_checks_search_evasions() {
+        // Nc7+ forks king and rook: whichever way the king steps, Nxa8 wins
+        // the rook back, which is only seen by searching every evasion.
+        let board = fen::decode("r3k3/8/8/3N4/8/8/8/4K3 w - - 0 1").unwrap();
+        let result = Search::new(&board, 1, Scorer::eval).find();
+        assert_eq!(Some(Move::Slide { from: D5, to: C7, castling_update: None }), result.movement);
+        assert!(result.eval > 0);
+
+        // Black only sees the fork coming when White's checks are searched
+        // past the horizon.
+        let board = fen::decode("r3k3/p7/8/3N4/8/8/8/4K3 b - - 0 1").unwrap();
+        let without = Search::new(&board, 1, Scorer::eval).find();
+        let with = Search::new(&board, 1, Scorer::eval).with_quiescence_checks(true).find();
+        assert!(without.movement.is_some_and(|m| m.from() != A8 && m.from() != E8));
+        assert!(with.movement.is_some_and(|m| m.from() == A8 || m.from() == E8));
+    }
+
+    #[test]
+    fn iterative_deepening() {
+        let board = fen::decode("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+
+        let result = Search::new(&board, 3, Scorer::eval).find();
+        assert_eq!(3, result.depth);
+        assert_eq!(3, result.iterations.len());
+        assert_eq!(Some(&result.eval), result.iterations.last());
+        assert_eq!(result.movement, result.pv.first().copied());
+        assert_eq!(3, result.pv.len());
+    }
+
+    #[test]
+    fn pv_leads_to_mate() {
+        let mut board = fen::decode("8/8/8/2Q5/7p/1k5P/1N6/1K3B2 w - - 0 101").unwrap();
+
+        let result = Search::new(&board, 4, Scorer::eval).find();
+        assert_eq!(Some(3), result.mate_dist);
+        assert_eq!(3, result.depth);
+        assert_eq!(3, result.pv.len());
+
+        result.pv.iter().for_each(|m| board.apply_mut(*m));
+        let mover = board.state().mover();
+        assert!(board.movements(mover).is_empty());
+        assert!(board.in_check(mover));
+    }
+
+    #[test]
+    fn node_limit() {
+        let board = Board::default();
+        let limits = SearchLimits { nodes: Some(2_000), ..SearchLimits::default() };
+
+        let (result, stats) = Search::from_limits(&board, limits, Scorer::eval).find_with_stats();
+        assert!(result.movement.is_some());
+        assert_eq!(result.depth, result.iterations.len());
+        assert!(stats.nodes + stats.qnodes <= 2_000 + 1);
+    }
+
+    #[test]
+    fn stop_flag_falls_back_to_legal_move() {
+        let board = Board::default();
+        let stop = Arc::new(AtomicBool::new(true));
+        let limits = SearchLimits { stop: Some(stop), ..SearchLimits::default() };
+
+        let (result, stats) = Search::from_limits(&board, limits, Scorer::eval).find_with_stats();
+        assert!(result.movement.is_some());
+        assert_eq!(0, result.depth);
+        assert_eq!(1, stats.nodes);
+    }
+
+    #[test]
+    fn node_limit_bounds_first_iteration() {
+        let board =
+            fen::decode("r1bq1rk1/pp2bppp/2n1pn2/2pp4/2PP4/2N1PN2/PP1BBPPP/R2QK2R w KQ - 0 8")
+                .unwrap();
+        let limits = SearchLimits { nodes: Some(100), ..SearchLimits::default() };
+
+        let (result, stats) = Search::from_limits(&board, limits, Scorer::eval).find_with_stats();
+        assert!(result.movement.is_some_and(|m| board.movements(Color::W).contains(&m)));
+        assert_eq!(0, result.depth);
+        assert!(stats.nodes + stats.qnodes <= 100);
+    }
+
+    #[test]
+    fn expired_deadline() {
+        let board = Board::default();
+        let limits = SearchLimits {
+            depth: Some(3),
+            movetime: Some(Duration::ZERO),
+            ..SearchLimits::default()
+        };
+
+        let result = Search::from_limits(&board, limits, Scorer::eval).find();
+        assert!(result.movement.is_some());
+        assert_eq!(1, result.depth);
+    }
+
+    #[test]
+    fn forced_move_returns_after_first_iteration() {
+        let board = fen::decode("k7/8/8/8/8/8/8/1R5K b - - 0 1").unwrap();
+        let clock = Clock::new(Duration::from_secs(60), Duration::ZERO);
+        let time = TimeManager::new(&clock, Color::B);
+
+        let result = Search::new(&board, 10, Scorer::eval).with_time_manager(time).find();
+        assert_eq!(Some(Move::Slide { from: A8, to: A7, castling_update: None }), result.movement);
+        assert_eq!(1, result.depth);
+    }
+
+    #[test]
+    fn time_manager_bounds_search() {
+        let board = Board::default();
+        let clock = Clock::new(Duration::from_millis(200), Duration::ZERO);
+        let time = TimeManager::new(&clock, Color::W);
+
+        let start = Instant::now();
+        let result = Search::new(&board, 20, Scorer::eval).with_time_manager(time).find();
+        assert!(result.movement.is_some());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn progress_reports_iterations() {
+        let board = Board::default();
+        let mut depths = Vec::new();
+
+        let result =
+            Search::new(&board, 3, Scorer::eval).with_progress(|r, _| depths.push(r.depth)).find();
+        assert_eq!(3, result.depth);
+        assert_eq!(vec![1, 2, 3], depths);
+    }
+
     #[test]
     fn mate_in_two() {
         let mut board = fen::decode("8/8/8/2Q5/7p/1k5P/1N6/1K3B2 w - - 0 101").expect("ook");
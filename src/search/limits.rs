@@ -0,0 +1,80 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// Bounds on how long a search may run. Unset limits are ignored; once any
+/// limit is hit the search returns the result of its deepest completed
+/// iteration, or the best move found so far when none has completed.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    /// Deepest iteration to search.
+    pub depth: Option<usize>,
+    /// Nodes to visit, counting quiescence nodes.
+    pub nodes: Option<usize>,
+    /// Time to spend, measured from the start of the search.
+    pub movetime: Option<Duration>,
+    /// Point in time by which the search must have returned.
+    pub deadline: Option<Instant>,
+    /// Flag raised by another thread to cancel the search.
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+impl SearchLimits {
+    #[must_use]
+    pub fn depth(depth: usize) -> Self {
+        Self { depth: Some(depth), ..Self::default() }
+    }
+
+    /// Earliest of `deadline` and `movetime` counted from `start`.
+    #[must_use]
+    pub(crate) fn deadline_from(&self, start: Instant) -> Option<Instant> {
+        let movetime = self.movetime.map(|movetime| start + movetime);
+        match (self.deadline, movetime) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deadline_from() {
+        let start = Instant::now();
+        let later = start + Duration::from_secs(10);
+
+        assert_eq!(None, SearchLimits::default().deadline_from(start));
+
+        let sut = SearchLimits { deadline: Some(later), ..SearchLimits::default() };
+        assert_eq!(Some(later), sut.deadline_from(start));
+
+        let sut = SearchLimits {
+            deadline: Some(later),
+            movetime: Some(Duration::from_secs(1)),
+            ..SearchLimits::default()
+        };
+        assert_eq!(Some(start + Duration::from_secs(1)), sut.deadline_from(start));
+    }
+
+    #[test]
+    fn is_stopped() {
+        let stop = Arc::new(AtomicBool::new(false));
+        let sut = SearchLimits { stop: Some(stop.clone()), ..SearchLimits::default() };
+        assert!(!sut.is_stopped());
+
+        stop.store(true, Ordering::Relaxed);
+        assert!(sut.is_stopped());
+        assert!(!SearchLimits::default().is_stopped());
+    }
+}
@@ -0,0 +1,212 @@
+use std::time::Duration;
+
+use crate::color::Color;
+
+/// Time kept in reserve to cover the overhead of communicating a move.
+const OVERHEAD: Duration = Duration::from_millis(30);
+
+/// Moves assumed to remain in the game when the time control does not say.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// How far past the soft budget the hard budget reaches.
+const HARD_FACTOR: u32 = 4;
+
+/// Drop in score between iterations that is worth spending more time on.
+const SCORE_DROP: i32 = 30;
+
+/// Remaining time of both sides in a game played under a clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Clock {
+    pub white: Duration,
+    pub black: Duration,
+    pub white_increment: Duration,
+    pub black_increment: Duration,
+    /// Moves until the next time control, `None` for sudden death.
+    pub moves_to_go: Option<u32>,
+}
+
+impl Clock {
+    /// Clock for a game where both sides start with `time` and receive
+    /// `increment` after each move.
+    #[must_use]
+    pub fn new(time: Duration, increment: Duration) -> Self {
+        Self {
+            white: time,
+            black: time,
+            white_increment: increment,
+            black_increment: increment,
+            moves_to_go: None,
+        }
+    }
+
+    #[must_use]
+    pub(crate) const fn remaining(&self, color: Color) -> Duration {
+        match color {
+            Color::B => self.black,
+            Color::W => self.white,
+        }
+    }
+
+    #[must_use]
+    pub(crate) const fn increment(&self, color: Color) -> Duration {
+        match color {
+            Color::B => self.black_increment,
+            Color::W => self.white_increment,
+        }
+    }
+
+    /// Charges `elapsed` to the clock of `color` and credits its increment.
+    /// Returns false when the flag fell.
+    pub(crate) fn punch(&mut self, color: Color, elapsed: Duration) -> bool {
+        let increment = self.increment(color);
+        let remaining = match color {
+            Color::B => &mut self.black,
+            Color::W => &mut self.white,
+        };
+        if elapsed > *remaining {
+            *remaining = Duration::ZERO;
+            return false;
+        }
+        *remaining = *remaining - elapsed + increment;
+        true
+    }
+}
+
+/// Splits the clock into a budget for the current move. The soft budget is
+/// the target, checked between iterations and stretched when the search is
+/// unstable; the hard budget is never exceeded.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeManager {
+    soft: Duration,
+    hard: Duration,
+    scale: u32,
+    forced: bool,
+}
+
+impl TimeManager {
+    #[must_use]
+    pub fn new(clock: &Clock, mover: Color) -> Self {
+        let available = clock.remaining(mover).saturating_sub(OVERHEAD);
+        let moves_to_go = clock.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let soft = (available / moves_to_go + clock.increment(mover) * 3 / 4).min(available);
+        let hard = (soft * HARD_FACTOR).min(available);
+        Self { soft, hard, scale: 100, forced: false }
+    }
+
+    #[must_use]
+    pub fn soft(&self) -> Duration {
+        self.soft * self.scale / 100
+    }
+
+    #[must_use]
+    pub const fn hard(&self) -> Duration {
+        self.hard
+    }
+
+    /// Marks the move as forced, so no time is spent beyond the first
+    /// iteration.
+    pub(crate) fn set_forced(&mut self, forced: bool) {
+        self.forced = forced;
+    }
+
+    /// Adjusts the budget after an iteration: a new best move or a falling
+    /// score call for more time, a stable search gives some of it back.
+    pub(crate) fn update(&mut self, best_move_changed: bool, score_drop: i32) {
+        if best_move_changed {
+            self.scale += 50;
+        } else {
+            self.scale = (self.scale * 9 / 10).max(60);
+        }
+        if score_drop >= SCORE_DROP {
+            self.scale += 30;
+        }
+        self.scale = self.scale.min(100 * HARD_FACTOR);
+    }
+
+    /// Whether to stop instead of starting another iteration. An iteration
+    /// usually takes longer than all previous ones together, so there is no
+    /// point in starting one past half the budget.
+    #[must_use]
+    pub(crate) fn should_stop(&self, elapsed: Duration) -> bool {
+        self.forced || elapsed >= self.soft().min(self.hard) / 2
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn sudden_death() {
+        let clock = Clock { white: secs(60) + OVERHEAD, ..Clock::default() };
+        let sut = TimeManager::new(&clock, Color::W);
+        assert_eq!(secs(2), sut.soft());
+        assert_eq!(secs(8), sut.hard());
+    }
+
+    #[test]
+    fn increment() {
+        let clock = Clock::new(secs(60) + OVERHEAD, secs(4));
+        let sut = TimeManager::new(&clock, Color::B);
+        assert_eq!(secs(5), sut.soft());
+        assert_eq!(secs(20), sut.hard());
+    }
+
+    #[test]
+    fn moves_to_go() {
+        let clock = Clock { white: secs(10) + OVERHEAD, moves_to_go: Some(1), ..Clock::default() };
+        let sut = TimeManager::new(&clock, Color::W);
+        assert_eq!(secs(10), sut.soft());
+        assert_eq!(secs(10), sut.hard());
+    }
+
+    #[test]
+    fn low_on_time() {
+        let clock = Clock::new(Duration::from_millis(10), secs(1));
+        let sut = TimeManager::new(&clock, Color::W);
+        assert_eq!(Duration::ZERO, sut.hard());
+    }
+
+    #[test]
+    fn update_extends_and_shrinks() {
+        let clock = Clock::new(secs(60) + OVERHEAD, Duration::ZERO);
+        let mut sut = TimeManager::new(&clock, Color::W);
+
+        sut.update(true, 0);
+        assert_eq!(secs(3), sut.soft());
+        assert!(!sut.should_stop(Duration::from_millis(1400)));
+        assert!(sut.should_stop(Duration::from_millis(1500)));
+
+        sut.update(false, SCORE_DROP);
+        assert!(sut.soft() > secs(3));
+
+        for _ in 0..20 {
+            sut.update(false, 0);
+        }
+        assert_eq!(Duration::from_millis(1200), sut.soft());
+        assert!(sut.should_stop(Duration::from_millis(600)));
+    }
+
+    #[test]
+    fn forced_stops() {
+        let clock = Clock::new(secs(60), Duration::ZERO);
+        let mut sut = TimeManager::new(&clock, Color::W);
+        sut.set_forced(true);
+        assert!(sut.should_stop(Duration::ZERO));
+    }
+
+    #[test]
+    fn punch() {
+        let mut sut = Clock::new(secs(10), secs(1));
+        assert!(sut.punch(Color::W, secs(3)));
+        assert_eq!(secs(8), sut.white);
+        assert_eq!(secs(10), sut.black);
+
+        assert!(!sut.punch(Color::B, secs(11)));
+        assert_eq!(Duration::ZERO, sut.black);
+    }
+}
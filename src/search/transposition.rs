@@ -0,0 +1,151 @@
+use std::mem;
+
+use crate::moves::Move;
+
+use super::MATE_BOUND;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Entry {
+    pub(crate) key: u64,
+    pub(crate) depth: u8,
+    pub(crate) bound: Bound,
+    pub(crate) eval: i32,
+    pub(crate) movement: Option<Move>,
+}
+
+/// Fixed-size hash table of previously searched positions, indexed by their
+/// Zobrist key. Each slot keeps a single entry, replaced by deeper or newer
+/// searches of a different position.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    pub const DEFAULT_MB: usize = 16;
+
+    #[must_use]
+    pub fn new(mb: usize) -> Self {
+        let len = (mb * 1024 * 1024 / mem::size_of::<Option<Entry>>()).max(1);
+        Self { entries: vec![None; len] }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    /// Returns the entry stored for `key`, with mate scores relative to `ply`.
+    #[must_use]
+    pub(crate) fn probe(&self, key: u64, ply: usize) -> Option<Entry> {
+        self.entries[self.index(key)]
+            .filter(|entry| entry.key == key)
+            .map(|entry| Entry { eval: from_table(entry.eval, ply), ..entry })
+    }
+
+    /// Stores the result of searching `key` to `depth` plies at `ply`. Mate
+    /// scores are kept relative to the stored position so they remain valid
+    /// when the position is reached through a different path.
+    pub(crate) fn store(
+        &mut self,
+        key: u64,
+        ply: usize,
+        depth: usize,
+        bound: Bound,
+        eval: i32,
+        movement: Option<Move>,
+    ) {
+        let depth = u8::try_from(depth).unwrap_or(u8::MAX);
+        let idx = self.index(key);
+        if let Some(existing) = self.entries[idx]
+            && existing.key == key
+            && existing.depth > depth
+        {
+            return;
+        }
+        self.entries[idx] = Some(Entry { key, depth, bound, eval: to_table(eval, ply), movement });
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MB)
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+const fn to_table(eval: i32, ply: usize) -> i32 {
+    if eval >= MATE_BOUND {
+        eval + ply as i32
+    } else if eval <= -MATE_BOUND {
+        eval - ply as i32
+    } else {
+        eval
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+const fn from_table(eval: i32, ply: usize) -> i32 {
+    if eval >= MATE_BOUND {
+        eval - ply as i32
+    } else if eval <= -MATE_BOUND {
+        eval + ply as i32
+    } else {
+        eval
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{eval::MATE_SCORE, squares::*};
+
+    #[test]
+    fn store_and_probe() {
+        let mut sut = TranspositionTable::new(1);
+        let movement = Some(Move::Slide { from: E2, to: E4, castling_update: None });
+        assert!(sut.probe(42, 0).is_none());
+
+        sut.store(42, 0, 3, Bound::Lower, 25, movement);
+        let entry = sut.probe(42, 0).unwrap();
+        assert_eq!(3, entry.depth);
+        assert_eq!(Bound::Lower, entry.bound);
+        assert_eq!(25, entry.eval);
+        assert_eq!(movement, entry.movement);
+
+        sut.clear();
+        assert!(sut.probe(42, 0).is_none());
+    }
+
+    #[test]
+    fn keeps_deeper_entry() {
+        let mut sut = TranspositionTable::new(1);
+        sut.store(42, 0, 5, Bound::Exact, 10, None);
+        sut.store(42, 0, 2, Bound::Exact, 20, None);
+        assert_eq!(10, sut.probe(42, 0).unwrap().eval);
+
+        sut.store(42, 0, 6, Bound::Exact, 30, None);
+        assert_eq!(30, sut.probe(42, 0).unwrap().eval);
+    }
+
+    #[test]
+    fn mate_scores_are_relative_to_ply() {
+        let mut sut = TranspositionTable::new(1);
+        // Mate found 5 plies from the root, stored from a node at ply 3.
+        sut.store(42, 3, 4, Bound::Exact, MATE_SCORE - 5, None);
+
+        // Reached again at ply 1, the mate is now 3 plies from the root.
+        assert_eq!(MATE_SCORE - 3, sut.probe(42, 1).unwrap().eval);
+        assert_eq!(-MATE_SCORE + 3, from_table(to_table(-MATE_SCORE + 5, 3), 1));
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    io::{self, BufRead, Write},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    board::Board,
+    eval::{MATE_SCORE, Scorer},
+    fen,
+    moves::Move,
+    search::{Clock, Search, SearchLimits, SearchResult, Stats, TimeManager, TranspositionTable},
+};
+
+const MAX_HASH_MB: usize = 1024;
+
+/// Speaks the Universal Chess Interface over stdin and stdout until `quit`.
+pub fn run() {
+    let mut uci = Uci::new(io::stdout());
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !uci.handle(&line) {
+            break;
+        }
+    }
+    uci.stop();
+}
+
+struct Running {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<TranspositionTable>,
+}
+
+pub(crate) struct Uci<W: Write + Send + 'static> {
+    board: Board,
+    hash_mb: usize,
+    /// Whether castling is written as the king taking its own rook.
+    chess960: bool,
+    tt: Option<TranspositionTable>,
+    running: Option<Running>,
+    out: Arc<Mutex<W>>,
+}
+
+impl<W: Write + Send + 'static> Uci<W> {
+    pub(crate) fn new(out: W) -> Self {
+        Self {
+            board: Board::default(),
+            hash_mb: TranspositionTable::DEFAULT_MB,
+            chess960: false,
+            tt: Some(TranspositionTable::default()),
+            running: None,
+            out: Arc::new(Mutex::new(out)),
+        }
+    }
+
+    /// Handles a single command, returning false once the engine must quit.
+    pub(crate) fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                self.send("id name chessie");
+                self.send("id author the chessie authors");
+                self.send(&format!(
+                    "option name Hash type spin default {} min 1 max {MAX_HASH_MB}",
+                    TranspositionTable::DEFAULT_MB
+                ));
+                self.send("option name UCI_Chess960 type check default false");
+                self.send("uciok");
+            }
+            Some("isready") => self.send("readyok"),
+            Some("ucinewgame") => {
+                self.stop();
+                self.board = Board::default();
+                if let Some(tt) = self.tt.as_mut() {
+                    tt.clear();
+                }
+            }
+            Some("setoption") => self.set_option(&tokens.collect::<Vec<_>>()),
+            Some("position") => {
+                self.stop();
+                if let Err(err) = self.set_position(&tokens.collect::<Vec<_>>()) {
+                    self.send(&format!("info string {err}"));
+                }
+            }
+            Some("go") => self.go(&tokens.collect::<Vec<_>>()),
+            Some("stop") => self.stop(),
+            Some("quit") => {
+                self.stop();
+                return false;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Signals a running search to stop and waits for its `bestmove`.
+    pub(crate) fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.stop.store(true, Ordering::Relaxed);
+            running.handle.thread().unpark();
+            self.tt = running.handle.join().ok();
+        }
+    }
+
+    fn set_option(&mut self, tokens: &[&str]) {
+        let name = tokens.iter().position(|t| *t == "name");
+        let value = tokens.iter().position(|t| *t == "value");
+        let (Some(name), Some(value)) = (name, value) else {
+            return;
+        };
+        let name = tokens[name + 1..value].join(" ");
+        let value = tokens.get(value + 1).copied().unwrap_or_default();
+
+        if name.eq_ignore_ascii_case("hash")
+            && let Ok(mb) = value.parse::<usize>()
+        {
+            self.stop();
+            self.hash_mb = mb.clamp(1, MAX_HASH_MB);
+            self.tt = Some(TranspositionTable::new(self.hash_mb));
+        } else if name.eq_ignore_ascii_case("UCI_Chess960") {
+            self.chess960 = value.eq_ignore_ascii_case("true");
+        }
+    }
+
+    fn set_position(&mut self, tokens: &[&str]) -> Result<(), String> {
+        let moves_at = tokens.iter().position(|t| *t == "moves").unwrap_or(tokens.len());
+        let mut board = match tokens.first() {
+            Some(&"startpos") => Board::default(),
+            Some(&"fen") => {
+                fen::decode(&tokens[1..moves_at].join(" ")).map_err(|err| err.to_string())?
+            }
+            _ => return Err("expected startpos or fen".to_string()),
+        };
+
+        for text in tokens.iter().skip(moves_at + 1) {
+            let movement =
+                board.parse_uci_move(text).ok_or_else(|| format!("illegal move {text}"))?;
+            board.apply_mut(movement);
+        }
+        self.board = board;
+        Ok(())
+    }
+
+    fn go(&mut self, tokens: &[&str]) {
+        self.stop();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut limits = SearchLimits { stop: Some(stop.clone()), ..SearchLimits::default() };
+        let mut clock = Clock::default();
+        let mut timed = false;
+        let mut infinite = false;
+
+        let mut tokens = tokens.iter();
+        while let Some(token) = tokens.next() {
+            let mut value = || tokens.next().and_then(|v| v.parse::<u64>().ok()).unwrap_or(0);
+            match *token {
+                "depth" => limits.depth = Some(value() as usize),
+                "nodes" => limits.nodes = Some(value() as usize),
+                "movetime" => limits.movetime = Some(Duration::from_millis(value())),
+                "wtime" => (clock.white, timed) = (Duration::from_millis(value()), true),
+                "btime" => (clock.black, timed) = (Duration::from_millis(value()), true),
+                "winc" => clock.white_increment = Duration::from_millis(value()),
+                "binc" => clock.black_increment = Duration::from_millis(value()),
+                "movestogo" => clock.moves_to_go = u32::try_from(value()).ok(),
+                "infinite" => infinite = true,
+                _ => {}
+            }
+        }
+
+        let board = self.board.clone();
+        let uci: fn(Move) -> String =
+            if self.chess960 { Move::to_uci_chess960 } else { Move::to_uci };
+        let mut tt = self.tt.take().unwrap_or_else(|| TranspositionTable::new(self.hash_mb));
+        let out = self.out.clone();
+        let stopped = stop.clone();
+        let handle = thread::spawn(move || {
+            let mut search = Search::from_limits(&board, limits, Scorer::eval)
+                .with_table(&mut tt)
+                .with_progress(|result, stats| {
+                    write_line(&out, &info(result, stats, uci));
+                });
+            if timed && !infinite {
+                search = search.with_time_manager(TimeManager::new(&clock, board.state().mover()));
+            }
+            let result = search.find();
+            // An infinite search may finish early, e.g. on finding a mate, but
+            // `bestmove` must still wait for `stop`.
+            while infinite && !stopped.load(Ordering::Relaxed) {
+                thread::park();
+            }
+            let bestmove = result.movement.map_or_else(|| "0000".to_string(), uci);
+            write_line(&out, &format!("bestmove {bestmove}"));
+            tt
+        });
+        self.running = Some(Running { stop, handle });
+    }
+
+    fn send(&self, line: &str) {
+        write_line(&self.out, line);
+    }
+}
+
+fn write_line<W: Write>(out: &Mutex<W>, line: &str) {
+    if let Ok(mut out) = out.lock() {
+        let _ = writeln!(out, "{line}");
+        let _ = out.flush();
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+fn info(result: &SearchResult, stats: &Stats, uci: fn(Move) -> String) -> String {
+    let score = match result.mate_dist {
+        Some(dist) if result.eval > 0 => format!("mate {}", dist.div_ceil(2)),
+        Some(dist) => format!("mate -{}", dist.div_ceil(2)),
+        None => format!("cp {}", result.eval.clamp(-MATE_SCORE, MATE_SCORE)),
+    };
+    let nodes = stats.nodes + stats.qnodes;
+    let millis = stats.elapsed.as_millis();
+    let nps = (nodes as f64 / stats.elapsed.as_secs_f64().max(0.001)) as u64;
+    let pv: Vec<_> = result.pv.iter().copied().map(uci).collect();
+    format!(
+        "info depth {} score {score} nodes {nodes} nps {nps} time {millis} pv {}",
+        result.depth,
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Output {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(String::from)
+                .collect()
+        }
+    }
+
+    fn sut() -> (Uci<Output>, Output) {
+        let out = Output::default();
+        (Uci::new(out.clone()), out)
+    }
+
+    /// Waits for the running search to finish on its own, without `stop`.
+    fn finish(sut: &mut Uci<Output>) {
+        if let Some(running) = sut.running.take() {
+            sut.tt = running.handle.join().ok();
+        }
+    }
+
+    #[test]
+    fn handshake() {
+        let (mut sut, out) = sut();
+        assert!(sut.handle("uci"));
+        assert!(sut.handle("isready"));
+
+        let lines = out.lines();
+        assert_eq!("id name chessie", lines[0]);
+        assert!(lines.contains(&"uciok".to_string()));
+        assert_eq!("readyok", lines.last().unwrap());
+    }
+
+    #[test]
+    fn position_startpos_with_moves() {
+        let (mut sut, _) = sut();
+        sut.handle("position startpos moves e2e4 e7e5 g1f3");
+        assert_eq!(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            fen::encode(&sut.board)
+        );
+    }
+
+    #[test]
+    fn position_fen_with_promotion() {
+        let (mut sut, _) = sut();
+        sut.handle("position fen 8/P7/8/8/8/8/8/k6K w - - 0 1 moves a7a8n");
+        assert_eq!("N7/8/8/8/8/8/8/k6K b - - 0 1", fen::encode(&sut.board));
+    }
+
+    #[test]
+    fn illegal_move_keeps_position() {
+        let (mut sut, out) = sut();
+        sut.handle("position startpos moves e2e5");
+        assert_eq!(fen::encode(&Board::default()), fen::encode(&sut.board));
+        assert_eq!("info string illegal move e2e5", out.lines()[0]);
+    }
+
+    #[test]
+    fn go_depth() {
+        let (mut sut, out) = sut();
+        sut.handle("position fen 8/8/8/8/2Q4p/k6P/1N6/1K3B2 w - - 0 101");
+        sut.handle("go depth 2");
+        finish(&mut sut);
+
+        let lines = out.lines();
+        assert!(lines[0].starts_with("info depth 1 score mate 1 nodes"));
+        assert!(lines[0].ends_with("pv c4c3"));
+        assert_eq!("bestmove c4c3", lines.last().unwrap());
+    }
+
+    #[test]
+    fn go_infinite_until_stop() {
+        let (mut sut, out) = sut();
+        sut.handle("position startpos");
+        sut.handle("go infinite");
+        thread::sleep(Duration::from_millis(50));
+        sut.handle("stop");
+
+        assert!(out.lines().last().unwrap().starts_with("bestmove "));
+        assert!(sut.tt.is_some());
+
+        sut.handle("position fen 8/8/8/8/2Q4p/k6P/1N6/1K3B2 w - - 0 101");
+        sut.handle("go infinite");
+        thread::sleep(Duration::from_millis(50));
+        assert!(!out.lines().last().unwrap().starts_with("bestmove "));
+
+        sut.handle("stop");
+        assert_eq!("bestmove c4c3", out.lines().last().unwrap());
+    }
+
+    #[test]
+    fn set_hash() {
+        let (mut sut, _) = sut();
+        sut.handle("setoption name Hash value 1");
+        assert_eq!(1, sut.hash_mb);
+    }
+
+    #[test]
+    fn chess960() {
+        let (mut sut, out) = sut();
+        sut.handle("setoption name UCI_Chess960 value true");
+        assert!(sut.chess960);
+
+        sut.handle("position fen 7k/8/8/8/8/8/PP6/RK6 w A - 0 1 moves b1a1");
+        assert_eq!("7k/8/8/8/8/8/PP6/2KR4 b - - 1 1", fen::encode(&sut.board));
+
+        sut.handle("position fen 2rkr3/2p1p3/8/8/8/8/PP6/RK6 w A - 0 1");
+        sut.handle("go depth 2");
+        finish(&mut sut);
+        assert_eq!("bestmove b1a1", out.lines().last().unwrap());
+    }
+
+    #[test]
+    fn quit() {
+        let (mut sut, _) = sut();
+        assert!(!sut.handle("quit"));
+    }
+}